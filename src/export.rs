@@ -0,0 +1,457 @@
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use ron::ser::{self, PrettyConfig};
+use serde::Serialize;
+use url::Url;
+
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{
+    history::CompletionRecord,
+    job::Job,
+    job_board::{JobBoard, SuspendedStack},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Ical,
+    Json,
+    Csv,
+    Ron,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ical" | "ics" => Ok(ExportFormat::Ical),
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "ron" => Ok(ExportFormat::Ron),
+            _ => anyhow::bail!(
+                "Unknown export format \"{}\". Expected one of: ical, json, csv, ron",
+                s
+            ),
+        }
+    }
+}
+
+/// Formats the completion history can be exported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    Csv,
+}
+
+impl FromStr for HistoryFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(HistoryFormat::Csv),
+            _ => anyhow::bail!("Unknown history format \"{}\". Expected one of: csv", s),
+        }
+    }
+}
+
+/// Whether `date` falls within `since..until`, where a missing bound is unbounded.
+pub fn in_range(
+    date: DateTime<Utc>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+) -> bool {
+    since.is_none_or(|since| date >= since) && until.is_none_or(|until| date < until)
+}
+
+pub fn export_history(
+    records: &[CompletionRecord],
+    format: HistoryFormat,
+) -> anyhow::Result<String> {
+    match format {
+        HistoryFormat::Csv => history_csv(records),
+    }
+}
+
+#[derive(Serialize)]
+struct HistoryRow<'a> {
+    label: &'a str,
+    tags: String,
+    began: String,
+    ended: String,
+    elapsed_secs: i64,
+    cancelled: bool,
+    estimate_secs: Option<u64>,
+}
+
+/// One row per finished job, for spreadsheets. Dates are RFC 3339 in UTC.
+fn history_csv<'a>(records: impl IntoIterator<Item = &'a CompletionRecord>) -> anyhow::Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in records {
+        writer
+            .serialize(HistoryRow {
+                label: &record.label,
+                tags: record.tags.join(" "),
+                began: record.begin_date.to_rfc3339(),
+                ended: record.end_date.to_rfc3339(),
+                elapsed_secs: record
+                    .end_date
+                    .signed_duration_since(record.begin_date)
+                    .num_seconds()
+                    .max(0),
+                cancelled: record.cancelled,
+                estimate_secs: record.estimate.map(|estimate| estimate.as_secs()),
+            })
+            .context("Failed to write history row.")?;
+    }
+    let bytes = writer
+        .into_inner()
+        .context("Failed to finish CSV export.")?;
+    String::from_utf8(bytes).context("CSV export was not valid UTF-8.")
+}
+
+/// Everything an export can contain, already narrowed to the requested dates.
+/// Finished jobs are kept by when they ended, active jobs by when they began,
+/// and suspended stacks by when they were suspended.
+#[derive(Serialize)]
+pub struct ExportData<'a> {
+    pub finished: Vec<&'a CompletionRecord>,
+    pub active: Vec<&'a Job>,
+    pub suspended: Vec<&'a SuspendedStack>,
+}
+
+impl<'a> ExportData<'a> {
+    /// Collects the finished jobs between `since` and `until`, and with
+    /// `include_board`, the active jobs and suspended stacks too.
+    pub fn new(
+        records: &'a [CompletionRecord],
+        board: &'a JobBoard,
+        include_board: bool,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Self {
+        let finished = records
+            .iter()
+            .filter(|record| in_range(record.end_date, since, until))
+            .collect();
+        if !include_board {
+            return ExportData {
+                finished,
+                active: Vec::new(),
+                suspended: Vec::new(),
+            };
+        }
+        ExportData {
+            finished,
+            active: board
+                .active_stack
+                .iter()
+                .filter(|job| in_range(job.begin_date, since, until))
+                .collect(),
+            suspended: board
+                .suspended_stacks
+                .iter()
+                .filter(|stack| in_range(stack.date_suspended, since, until))
+                .collect(),
+        }
+    }
+}
+
+/// Swaps the text in an export for placeholders like `task-1`, so the board's
+/// shape can be shared without what's on it. The same text always gets the same
+/// placeholder, so repeated jobs still look alike.
+#[derive(Default)]
+pub struct Anonymizer {
+    labels: HashMap<String, String>,
+    tags: HashMap<String, String>,
+    reasons: HashMap<String, String>,
+    links: usize,
+}
+
+fn placeholder(placeholders: &mut HashMap<String, String>, kind: &str, text: &str) -> String {
+    let next = placeholders.len() + 1;
+    placeholders
+        .entry(text.to_owned())
+        .or_insert_with(|| format!("{}-{}", kind, next))
+        .clone()
+}
+
+impl Anonymizer {
+    fn label(&mut self, label: &str) -> String {
+        placeholder(&mut self.labels, "task", label)
+    }
+
+    fn tag(&mut self, tag: &str) -> String {
+        placeholder(&mut self.tags, "tag", tag)
+    }
+
+    fn reason(&mut self, reason: &str) -> String {
+        // The default reason gives nothing away, and shows no reason was given
+        if reason == "None" {
+            return reason.to_owned();
+        }
+        placeholder(&mut self.reasons, "reason", reason)
+    }
+
+    fn job(&mut self, job: &mut Job) {
+        job.label = self.label(&job.label);
+        job.tags = job.tags.iter().map(|tag| self.tag(tag)).collect();
+        for link in &mut job.links {
+            self.links += 1;
+            let placeholder = format!("https://example.invalid/link-{}", self.links);
+            if let Ok(placeholder) = Url::parse(&placeholder) {
+                *link = placeholder;
+            }
+        }
+    }
+
+    pub fn board(&mut self, board: &mut JobBoard) {
+        board.context = board.context.as_deref().map(|tag| self.tag(tag));
+        for job in &mut board.active_stack {
+            self.job(job);
+        }
+        for stack in &mut board.suspended_stacks {
+            stack.reason = self.reason(&stack.reason);
+            for job in &mut stack.data {
+                self.job(job);
+            }
+        }
+    }
+
+    pub fn records(&mut self, records: &mut [CompletionRecord]) {
+        for record in records {
+            record.label = self.label(&record.label);
+            record.tags = record.tags.iter().map(|tag| self.tag(tag)).collect();
+            record.time_tag = record.time_tag.as_deref().map(|tag| self.tag(tag));
+            record.reason = record.reason.as_deref().map(|reason| self.reason(reason));
+        }
+    }
+}
+
+/// Renders an export. The calendar only has room for suspended stacks' timers,
+/// and the spreadsheet only for finished jobs; json and ron hold everything.
+pub fn export_data(data: &ExportData, format: ExportFormat) -> anyhow::Result<String> {
+    match format {
+        ExportFormat::Ical => Ok(ical(data.suspended.iter().copied())),
+        ExportFormat::Csv => history_csv(data.finished.iter().copied()),
+        ExportFormat::Json => serde_json::to_string_pretty(data)
+            .map(|text| text + "\n")
+            .context("Failed to serialize export."),
+        ExportFormat::Ron => ser::to_string_pretty(data, PrettyConfig::new())
+            .map(|text| text + "\n")
+            .context("Failed to serialize export."),
+    }
+}
+
+fn ical_date(date: DateTime<Utc>) -> String {
+    format!("{}", date.format("%Y%m%dT%H%M%SZ"))
+}
+
+fn ical_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Content lines longer than 75 octets must be folded onto
+// continuation lines that begin with a single space.
+fn push_ical_line(output: &mut String, line: &str) {
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > 75 {
+            output.push_str("\r\n ");
+            line_length = 1;
+        }
+        output.push(c);
+        line_length += c.len_utf8();
+    }
+    output.push_str("\r\n");
+}
+
+/// Renders every suspended stack with a timer as an iCalendar event,
+/// so the timers can be subscribed to from a calendar application.
+fn ical<'a>(stacks: impl IntoIterator<Item = &'a SuspendedStack>) -> String {
+    let now = ical_date(Utc::now());
+    let mut output = String::new();
+    push_ical_line(&mut output, "BEGIN:VCALENDAR");
+    push_ical_line(&mut output, "VERSION:2.0");
+    push_ical_line(&mut output, "PRODID:-//wyd//What You're Doing//EN");
+    for stack in stacks {
+        let timer = match stack.timer {
+            Some(timer) => timer,
+            None => continue,
+        };
+        let label = match stack.data.first() {
+            Some(job) => &job.label,
+            None => continue,
+        };
+        push_ical_line(&mut output, "BEGIN:VEVENT");
+        push_ical_line(
+            &mut output,
+            &format!(
+                "UID:{}-{}@wyd",
+                stack.date_suspended.timestamp(),
+                timer.timestamp()
+            ),
+        );
+        push_ical_line(&mut output, &format!("DTSTAMP:{}", now));
+        push_ical_line(&mut output, &format!("DTSTART:{}", ical_date(timer)));
+        push_ical_line(&mut output, &format!("SUMMARY:{}", ical_escape(label)));
+        push_ical_line(
+            &mut output,
+            &format!("DESCRIPTION:{}", ical_escape(&stack.reason)),
+        );
+        push_ical_line(&mut output, "END:VEVENT");
+    }
+    push_ical_line(&mut output, "END:VCALENDAR");
+    output
+}
+
+/// Bumped whenever a field of the JSON listing changes meaning or is removed.
+const LISTING_SCHEMA: u32 = 1;
+
+#[derive(Serialize)]
+struct JobListing<'a> {
+    id: &'a str,
+    label: &'a str,
+    begin: i64,
+    timebox_remaining: Option<i64>,
+    expired: bool,
+    tags: &'a [String],
+}
+
+impl<'a> JobListing<'a> {
+    fn new(job: &'a Job, now: DateTime<Utc>) -> Self {
+        JobListing {
+            id: &job.id,
+            label: &job.label,
+            begin: job.begin_date.timestamp(),
+            timebox_remaining: job
+                .timebox_expiry()
+                .map(|expiry| expiry.signed_duration_since(now).num_seconds()),
+            expired: job.timebox_expired(),
+            tags: &job.tags,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StackListing<'a> {
+    id: &'a str,
+    reason: &'a str,
+    timer: Option<i64>,
+    age: i64,
+    pinned: bool,
+    someday: bool,
+    jobs: Vec<JobListing<'a>>,
+}
+
+impl<'a> StackListing<'a> {
+    fn new(stack: &'a SuspendedStack, now: DateTime<Utc>) -> Self {
+        StackListing {
+            id: stack.id(),
+            reason: &stack.reason,
+            timer: stack.timer.map(|timer| timer.timestamp()),
+            age: stack.age(now).num_seconds(),
+            pinned: stack.pinned,
+            someday: stack.someday,
+            jobs: stack.data.iter().map(|job| JobListing::new(job, now)).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BoardListing<'a> {
+    schema: u32,
+    active: Vec<JobListing<'a>>,
+    suspended: Vec<StackListing<'a>>,
+}
+
+/// A stable, machine readable listing of both stacks for editor integrations.
+/// Dates are unix timestamps and durations are in seconds.
+pub fn json_listing(board: &JobBoard) -> anyhow::Result<String> {
+    let now = Utc::now();
+    let listing = BoardListing {
+        schema: LISTING_SCHEMA,
+        active: board
+            .active_stack
+            .iter()
+            .map(|job| JobListing::new(job, now))
+            .collect(),
+        suspended: board
+            .suspended_stacks
+            .iter()
+            .map(|stack| StackListing::new(stack, now))
+            .collect(),
+    };
+    serde_json::to_string_pretty(&listing).context("Failed to serialize job listing.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::time::Duration as StdDuration;
+
+    fn record(label: &str, tags: &[&str], minutes: i64) -> CompletionRecord {
+        CompletionRecord {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..CompletionRecord::finished_after(label, minutes)
+        }
+    }
+
+    #[test]
+    fn history_csv_reads_back() {
+        let mut cancelled = record("fix \"flaky\" test, again", &[], 5);
+        cancelled.cancelled = true;
+        cancelled.estimate = Some(StdDuration::from_secs(600));
+        let records = [record("write report", &["work", "docs"], 30), cancelled];
+        let csv = export_history(&records, HistoryFormat::Csv).unwrap();
+
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        assert_eq!(
+            headers.iter().collect::<Vec<_>>(),
+            ["label", "tags", "began", "ended", "elapsed_secs", "cancelled", "estimate_secs"]
+        );
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(
+            rows[0].iter().collect::<Vec<_>>(),
+            [
+                "write report",
+                "work docs",
+                "2021-06-04T09:00:00+00:00",
+                "2021-06-04T09:30:00+00:00",
+                "1800",
+                "false",
+                "",
+            ]
+        );
+        assert_eq!(&rows[1][0], "fix \"flaky\" test, again");
+        assert_eq!(&rows[1][4], "300");
+        assert_eq!(&rows[1][5], "true");
+        assert_eq!(&rows[1][6], "600");
+    }
+
+    #[test]
+    fn export_leaves_out_records_outside_the_range() {
+        let records = [
+            record("before", &[], 30),
+            record("starts before, ends inside", &[], 120),
+            record("on the bound", &[], 180),
+            record("after", &[], 600),
+        ];
+        let board = JobBoard::default();
+        let day = Utc.ymd(2021, 6, 4);
+        let since = Some(day.and_hms(10, 0, 0));
+        let until = Some(day.and_hms(12, 0, 0));
+        let data = ExportData::new(&records, &board, false, since, until);
+        let labels: Vec<&str> = data.finished.iter().map(|record| record.label.as_str()).collect();
+        assert_eq!(labels, ["starts before, ends inside"]);
+
+        let data = ExportData::new(&records, &board, false, since, None);
+        assert_eq!(data.finished.len(), 3);
+        let data = ExportData::new(&records, &board, false, None, until);
+        assert_eq!(data.finished.len(), 2);
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::{bail, Context};
+use chrono::{Duration, NaiveDate};
+use ron::ser::{self, PrettyConfig};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::BTreeMap, fmt, fs, path::Path, str::FromStr, time::Duration as StdDuration,
+};
+
+/// The things a daily goal can count.
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Debug)]
+pub enum GoalKind {
+    /// Finishing a number of tasks.
+    Tasks,
+    /// Spending some time focused in work mode.
+    Focus,
+}
+
+impl FromStr for GoalKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tasks" => Ok(GoalKind::Tasks),
+            "focus" => Ok(GoalKind::Focus),
+            _ => bail!("Unknown goal \"{}\". Expected one of: tasks, focus", s),
+        }
+    }
+}
+
+impl fmt::Display for GoalKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GoalKind::Tasks => "tasks",
+            GoalKind::Focus => "focus",
+        })
+    }
+}
+
+/// How far a day has come towards its goals.
+pub struct GoalProgress {
+    pub tasks_done: u32,
+    pub focused: Duration,
+}
+
+/// The goals set for a single day.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct DayGoals {
+    pub tasks: Option<u32>,
+    pub focus: Option<StdDuration>,
+    /// Goals that were already announced as met, so they're only celebrated once.
+    #[serde(default)]
+    pub celebrated: Vec<GoalKind>,
+}
+
+impl DayGoals {
+    /// Sets a goal from the text given on the command line, like "5" or "4h".
+    pub fn set(&mut self, kind: GoalKind, value: &str) -> anyhow::Result<()> {
+        match kind {
+            GoalKind::Tasks => {
+                let tasks: u32 = value
+                    .parse()
+                    .with_context(|| format!("Expected a number of tasks, got \"{}\"", value))?;
+                if tasks == 0 {
+                    bail!("A tasks goal needs at least 1 task.");
+                }
+                self.tasks = Some(tasks);
+            }
+            GoalKind::Focus => {
+                let focus = humantime::parse_duration(value).with_context(|| {
+                    format!("Expected a duration like \"4h\", got \"{}\"", value)
+                })?;
+                if focus.as_secs() == 0 {
+                    bail!("A focus goal needs some time to focus for.");
+                }
+                self.focus = Some(focus);
+            }
+        }
+        // A changed goal deserves its own celebration
+        self.celebrated.retain(|celebrated| *celebrated != kind);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_none() && self.focus.is_none()
+    }
+
+    /// Whether the goal is set and `progress` has reached it.
+    pub fn is_met(&self, kind: GoalKind, progress: &GoalProgress) -> bool {
+        match kind {
+            GoalKind::Tasks => matches!(self.tasks, Some(tasks) if progress.tasks_done >= tasks),
+            GoalKind::Focus => match self.focus.map(Duration::from_std) {
+                Some(Ok(focus)) => progress.focused >= focus,
+                _ => false,
+            },
+        }
+    }
+
+    /// Whether any goal is set that hasn't been celebrated yet.
+    pub fn has_uncelebrated(&self) -> bool {
+        let uncelebrated = |kind| !self.celebrated.contains(&kind);
+        (self.tasks.is_some() && uncelebrated(GoalKind::Tasks))
+            || (self.focus.is_some() && uncelebrated(GoalKind::Focus))
+    }
+
+    /// The goals that have been met, but not celebrated yet.
+    pub fn uncelebrated(&self, progress: &GoalProgress) -> Vec<GoalKind> {
+        [GoalKind::Tasks, GoalKind::Focus]
+            .iter()
+            .copied()
+            .filter(|kind| self.is_met(*kind, progress) && !self.celebrated.contains(kind))
+            .collect()
+    }
+
+    /// Describes progress towards each goal, like "3/5 tasks, 1h/4h focus".
+    pub fn summary(&self, progress: &GoalProgress) -> String {
+        let mut parts = Vec::new();
+        let tick = |kind| if self.is_met(kind, progress) { " ✓" } else { "" };
+        if let Some(tasks) = self.tasks {
+            parts.push(format!("{}/{} tasks{}", progress.tasks_done, tasks, tick(GoalKind::Tasks)));
+        }
+        if let Some(focus) = self.focus {
+            let focused = StdDuration::from_secs(progress.focused.num_seconds().max(0) as u64);
+            parts.push(format!(
+                "{}/{} focus{}",
+                humantime::format_duration(focused),
+                humantime::format_duration(focus),
+                tick(GoalKind::Focus)
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
+pub type Goals = BTreeMap<NaiveDate, DayGoals>;
+
+pub fn load(path: &Path) -> anyhow::Result<Goals> {
+    if !path.exists() {
+        return Ok(Goals::new());
+    }
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read goals file {:?}", path))?;
+    if contents.trim().is_empty() {
+        return Ok(Goals::new());
+    }
+    ron::from_str(&contents).with_context(|| format!("Goals file at {:?} is malformed.", path))
+}
+
+pub fn save(path: &Path, goals: &Goals) -> anyhow::Result<()> {
+    let text =
+        ser::to_string_pretty(goals, PrettyConfig::new()).context("Failed to serialize goals.")?;
+    fs::write(path, text).with_context(|| format!("Failed to write goals file {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(tasks_done: u32, focused_minutes: i64) -> GoalProgress {
+        GoalProgress {
+            tasks_done,
+            focused: Duration::minutes(focused_minutes),
+        }
+    }
+
+    #[test]
+    fn goals_are_met_at_the_boundary() {
+        let mut goals = DayGoals::default();
+        goals.set(GoalKind::Tasks, "5").unwrap();
+        goals.set(GoalKind::Focus, "4h").unwrap();
+
+        assert!(!goals.is_met(GoalKind::Tasks, &progress(4, 0)));
+        assert!(goals.is_met(GoalKind::Tasks, &progress(5, 0)));
+        assert!(!goals.is_met(GoalKind::Focus, &progress(0, 239)));
+        assert!(goals.is_met(GoalKind::Focus, &progress(0, 240)));
+        assert!(goals.uncelebrated(&progress(4, 239)).is_empty());
+        assert_eq!(goals.uncelebrated(&progress(5, 240)), [GoalKind::Tasks, GoalKind::Focus]);
+    }
+
+    #[test]
+    fn met_goals_are_celebrated_once() {
+        let mut goals = DayGoals::default();
+        goals.set(GoalKind::Tasks, "2").unwrap();
+        assert!(goals.has_uncelebrated());
+        goals.celebrated.extend(goals.uncelebrated(&progress(2, 0)));
+        assert!(!goals.has_uncelebrated());
+        assert!(goals.uncelebrated(&progress(3, 0)).is_empty());
+
+        // Raising the goal makes it worth celebrating again
+        goals.set(GoalKind::Tasks, "3").unwrap();
+        assert_eq!(goals.uncelebrated(&progress(3, 0)), [GoalKind::Tasks]);
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::Context;
+use chrono::{serde::ts_seconds, DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    time::Duration as StdDuration,
+};
+
+use crate::job_board::WorkState;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Debug)]
+pub enum FocusState {
+    Off,
+    Focused,
+    Slacking,
+}
+
+impl From<WorkState> for FocusState {
+    fn from(work_state: WorkState) -> Self {
+        match work_state {
+            WorkState::Off => FocusState::Off,
+            WorkState::Working => FocusState::Focused,
+            WorkState::SlackingSince(_) => FocusState::Slacking,
+            // Time on a break is neither focused nor slacking
+            WorkState::BreakUntil(_) => FocusState::Off,
+        }
+    }
+}
+
+/// Marks the moment work mode switched into a new state.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FocusRecord {
+    #[serde(with = "ts_seconds")]
+    pub date: DateTime<Utc>,
+    pub state: FocusState,
+}
+
+/// A job that was finished or cancelled.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CompletionRecord {
+    pub id: String,
+    pub label: String,
+    #[serde(with = "ts_seconds")]
+    pub begin_date: DateTime<Utc>,
+    #[serde(with = "ts_seconds")]
+    pub end_date: DateTime<Utc>,
+    /// How many jobs were below this one on the active stack.
+    pub depth: usize,
+    pub cancelled: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub estimate: Option<StdDuration>,
+    #[serde(default)]
+    pub timebox: Option<StdDuration>,
+    /// Credits the job's time to this tag in stats, instead of its own tags.
+    #[serde(default)]
+    pub time_tag: Option<String>,
+    /// Why the job was cancelled, from `wyd cancel --reason`.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[cfg(test)]
+impl CompletionRecord {
+    /// A finished job that began 2021-06-04 09:00 UTC and took `minutes`, for tests.
+    pub fn finished_after(label: &str, minutes: i64) -> Self {
+        use chrono::{Duration, TimeZone};
+        let begin_date = Utc.ymd(2021, 6, 4).and_hms(9, 0, 0);
+        CompletionRecord {
+            id: crate::job::new_id(),
+            label: label.to_owned(),
+            begin_date,
+            end_date: begin_date + Duration::minutes(minutes),
+            depth: 0,
+            cancelled: false,
+            tags: Vec::new(),
+            estimate: None,
+            timebox: None,
+            time_tag: None,
+            reason: None,
+        }
+    }
+}
+
+/// A note added with `wyd jot`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NoteRecord {
+    #[serde(with = "ts_seconds")]
+    pub date: DateTime<Utc>,
+    pub tag: Option<String>,
+    pub text: String,
+    /// The id of the job that was active when the note was written.
+    pub job_id: Option<String>,
+}
+
+// Record files hold one RON value per line, so new records
+// can be appended without rewriting the whole file.
+pub fn append_record<T: Serialize>(path: &Path, record: &T) -> anyhow::Result<()> {
+    let line = ron::to_string(record).context("Failed to serialize record.")?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open record file {:?}", path))?;
+    writeln!(file, "{}", line).with_context(|| format!("Failed to write to {:?}", path))?;
+    Ok(())
+}
+
+pub fn read_records<T: DeserializeOwned>(path: &Path) -> anyhow::Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read record file {:?}", path))?;
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            ron::from_str(line).with_context(|| format!("Malformed record in {:?}", path))
+        })
+        .collect()
+}
+
+/// Replaces the contents of a record file, e.g. to drop a record.
+pub fn write_records<T: Serialize>(path: &Path, records: &[T]) -> anyhow::Result<()> {
+    let mut contents = String::new();
+    for record in records {
+        contents.push_str(&ron::to_string(record).context("Failed to serialize record.")?);
+        contents.push('\n');
+    }
+    fs::write(path, contents).with_context(|| format!("Failed to write to {:?}", path))
+}
@@ -0,0 +1,170 @@
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+
+use std::{collections::HashSet, fs, path::Path, time::Duration as StdDuration};
+
+use crate::{
+    job::{Job, ReminderPolicy},
+    job_board::JobBoard,
+};
+
+/// Reads a job board saved as RON (like jobs.ron) or, for `.json` files, as JSON.
+pub fn read_board(path: &Path) -> anyhow::Result<JobBoard> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let is_json = matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some(extension) if extension.eq_ignore_ascii_case("json")
+    );
+    if is_json {
+        serde_json::from_str(&contents)
+            .with_context(|| format!("{:?} is not a valid job board", path))
+    } else {
+        ron::from_str(&contents).with_context(|| format!("{:?} is not a valid job board", path))
+    }
+}
+
+// Timers further out than this are assumed to be mistakes
+const MAX_YEARS_AHEAD: i64 = 100;
+
+fn check_duration(
+    errors: &mut Vec<String>,
+    job: &Job,
+    what: &str,
+    duration: Option<StdDuration>,
+) {
+    let duration = match duration {
+        Some(duration) => duration,
+        None => return,
+    };
+    let in_range = Duration::from_std(duration)
+        .ok()
+        .and_then(|duration| job.begin_date.checked_add_signed(duration))
+        .is_some();
+    if !in_range {
+        errors.push(format!("Job \"{}\" has an out of range {}.", job.label, what));
+    }
+}
+
+fn check_date(errors: &mut Vec<String>, label: &str, what: &str, date: DateTime<Utc>) {
+    let limit = Utc::now() + Duration::days(365 * MAX_YEARS_AHEAD);
+    if date > limit {
+        errors.push(format!(
+            "\"{}\" has a {} more than {} years away.",
+            label, what, MAX_YEARS_AHEAD
+        ));
+    }
+}
+
+/// Checks an imported board for anything serde accepts but the rest
+/// of wyd would choke on, returning every problem found.
+pub fn validate_board(board: &JobBoard) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut ids = HashSet::new();
+
+    for (index, stack) in board.suspended_stacks.iter().enumerate() {
+        match stack.data.first() {
+            Some(job) => {
+                if let Some(timer) = stack.timer {
+                    check_date(&mut errors, &job.label, "timer", timer);
+                }
+            }
+            None => errors.push(format!("Suspended stack {} has no jobs.", index + 1)),
+        }
+    }
+
+    for job in board.all_jobs() {
+        if job.label.trim().is_empty() {
+            errors.push(format!("Job #{} has an empty label.", job.id));
+        }
+        if !ids.insert(job.id.as_str()) {
+            errors.push(format!("More than one job has the id #{}.", job.id));
+        }
+        check_date(&mut errors, &job.label, "start date", job.begin_date);
+        check_duration(&mut errors, job, "timebox", job.timebox);
+        check_duration(&mut errors, job, "estimate", job.estimate);
+        if let Some(ReminderPolicy::Every(interval)) = job.reminder_policy {
+            check_duration(&mut errors, job, "reminder interval", Some(interval));
+        }
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job_board::SuspendOptions;
+
+    fn job(label: &str) -> Job {
+        Job::new(label.to_owned(), Utc::now())
+    }
+
+    #[test]
+    fn a_sound_board_passes() {
+        let mut board = JobBoard::default();
+        board.push(job("write tests"));
+        assert!(validate_board(&board).is_empty());
+    }
+
+    #[test]
+    fn empty_labels_are_rejected() {
+        let mut board = JobBoard::default();
+        let blank = job("  ");
+        let id = blank.id.clone();
+        board.push(blank);
+        assert_eq!(validate_board(&board), [format!("Job #{} has an empty label.", id)]);
+    }
+
+    #[test]
+    fn far_future_timers_are_rejected() {
+        let mut board = JobBoard::default();
+        let mut stack = SuspendOptions::default().into_stack(vec![job("someday")]);
+        stack.timer = Some(Utc::now() + Duration::days(365 * 200));
+        board.add_suspended_stack(stack);
+        assert_eq!(
+            validate_board(&board),
+            ["\"someday\" has a timer more than 100 years away."]
+        );
+    }
+
+    #[test]
+    fn out_of_range_durations_are_rejected() {
+        let mut board = JobBoard::default();
+        let mut endless = job("endless");
+        endless.timebox = Some(StdDuration::from_secs(u64::MAX));
+        endless.reminder_policy = Some(ReminderPolicy::Every(StdDuration::from_secs(u64::MAX)));
+        board.push(endless);
+        assert_eq!(
+            validate_board(&board),
+            [
+                "Job \"endless\" has an out of range timebox.",
+                "Job \"endless\" has an out of range reminder interval.",
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_ids_are_rejected() {
+        let mut board = JobBoard::default();
+        let original = job("original");
+        let copy = Job {
+            label: "copy".to_owned(),
+            ..original.clone()
+        };
+        let id = original.id.clone();
+        board.push(original);
+        board.add_suspended_stack(SuspendOptions::default().into_stack(vec![copy]));
+        assert_eq!(validate_board(&board), [format!("More than one job has the id #{}.", id)]);
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let mut board = JobBoard::default();
+        let mut broken = job("");
+        broken.estimate = Some(StdDuration::from_secs(u64::MAX));
+        board.push(broken.clone());
+        board.push(broken);
+        assert_eq!(validate_board(&board).len(), 5);
+    }
+}
@@ -1,23 +1,188 @@
 use chrono::{serde::ts_seconds, DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use url::Url;
+use uuid::Uuid;
+
+use std::{str::FromStr, time::Duration as StdDuration};
+
+/// A short id that's unambiguous enough to type, e.g. `wyd resume '#1a2b3c'`
+pub fn new_id() -> String {
+    Uuid::new_v4().to_simple().to_string()[..6].to_owned()
+}
+
+/// Tags are written as `@tag` on the command line, and stored without the `@`.
+pub fn parse_tag(word: &str) -> Option<&str> {
+    let tag = word.strip_prefix('@')?;
+    if is_valid_tag(tag) {
+        Some(tag)
+    } else {
+        None
+    }
+}
+
+pub fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty() && !tag.contains(|c: char| c.is_whitespace() || c == '@')
+}
+
+/// How often an expired task keeps reminding you.
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ReminderPolicy {
+    /// A single reminder, until the task is acted on.
+    Once,
+    /// A reminder at a fixed interval.
+    Every(StdDuration),
+    /// Reminders that grow further apart, up to a cap.
+    Backoff,
+}
+
+impl FromStr for ReminderPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "once" => Ok(ReminderPolicy::Once),
+            "backoff" => Ok(ReminderPolicy::Backoff),
+            interval => match humantime::parse_duration(interval) {
+                Ok(interval) => Ok(ReminderPolicy::Every(interval)),
+                Err(_) => anyhow::bail!(
+                    "Expected \"once\", \"backoff\", or an interval like \"5m\", got \"{}\"",
+                    s
+                ),
+            },
+        }
+    }
+}
+
+/// How much a job matters. Low priority jobs are busywork,
+/// which work mode doesn't count as focus.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl FromStr for Priority {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "low" => Ok(Priority::Low),
+            "normal" => Ok(Priority::Normal),
+            "high" => Ok(Priority::High),
+            _ => anyhow::bail!(
+                "Unknown priority \"{}\". Expected one of: low, normal, high",
+                s
+            ),
+        }
+    }
+}
+
+pub(crate) fn is_normal_priority(priority: &Priority) -> bool {
+    *priority == Priority::Normal
+}
+
+// Defaulted fields are left out of jobs.ron when they hold their default
+pub(crate) fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+pub(crate) fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
 
-use std::time::Duration as StdDuration;
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Job {
+    #[serde(default = "new_id")]
+    pub id: String,
     pub label: String,
     #[serde(with = "ts_seconds")]
     pub begin_date: DateTime<Utc>,
     pub timebox: Option<StdDuration>,
     pub last_notification: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "crate::job::is_zero")]
+    pub notification_count: u32,
+    /// Id of a task that must be finished before this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_by: Option<String>,
+    /// Overrides the default reminder backoff for this job.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminder_policy: Option<ReminderPolicy>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// How long you expected the job to take. Unlike the timebox, this never reminds you.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<StdDuration>,
+    /// Reference links, like the ticket or pull request the job is for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<Url>,
+    /// No reminders are sent for the job before this time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_until: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "crate::job::is_normal_priority")]
+    pub priority: Priority,
+    /// Set while a job pushed with `--start-paused` waits to be started.
+    /// Its clock doesn't run, and it sends no reminders, until `wyd start`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused_since: Option<DateTime<Utc>>,
 }
 
 impl Job {
-    fn timebox_remaining(&self) -> Option<StdDuration> {
+    /// A fresh job with a new id, begun at `begin_date`, with no timebox or other extras.
+    pub fn new(label: String, begin_date: DateTime<Utc>) -> Job {
+        Job {
+            id: new_id(),
+            label,
+            begin_date,
+            timebox: None,
+            last_notification: None,
+            notification_count: 0,
+            blocked_by: None,
+            reminder_policy: None,
+            tags: Vec::new(),
+            estimate: None,
+            links: Vec::new(),
+            quiet_until: None,
+            priority: Default::default(),
+            paused_since: None,
+        }
+    }
+
+    /// Whether working on the job counts as focus in work mode: it needs
+    /// a timebox, and can't be low priority busywork.
+    pub fn counts_as_focus(&self) -> bool {
+        self.timebox.is_some() && self.priority != Priority::Low && self.paused_since.is_none()
+    }
+
+    /// How long the job's clock had run by `now`. The clock stands still while
+    /// the job is paused, and a job that hasn't begun yet has taken no time.
+    pub fn elapsed(&self, now: DateTime<Utc>) -> Duration {
+        let clock_time = match self.paused_since {
+            Some(paused_since) => paused_since.min(now),
+            None => now,
+        };
+        clock_time.signed_duration_since(self.begin_date).max(Duration::zero())
+    }
+
+    /// Starts the clock of a paused job, so the time spent paused doesn't count.
+    /// Returns false if the job wasn't paused.
+    pub fn unpause(&mut self, now: DateTime<Utc>) -> bool {
+        match self.paused_since.take() {
+            Some(paused_since) => {
+                self.begin_date =
+                    self.begin_date + now.signed_duration_since(paused_since).max(Duration::zero());
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn timebox_remaining(&self) -> Option<StdDuration> {
         match self.timebox {
             Some(timebox) => {
-                let dur_result = (self.begin_date
-                    + Duration::from_std(timebox).expect("Duration out of range.")
-                    - Utc::now())
+                let dur_result = (Duration::from_std(timebox).expect("Duration out of range.")
+                    - self.elapsed(Utc::now()))
                 .to_std();
                 match dur_result {
                     Ok(dur) => Some(dur),
@@ -27,7 +192,89 @@ impl Job {
             None => None,
         }
     }
+    pub fn timebox_expiry(&self) -> Option<DateTime<Utc>> {
+        // Paused jobs can't expire until they're started
+        if self.paused_since.is_some() {
+            return None;
+        }
+        let timebox = Duration::from_std(self.timebox?).ok()?;
+        self.begin_date.checked_add_signed(timebox)
+    }
     pub fn timebox_expired(&self) -> bool {
         self.timebox_remaining() == Some(StdDuration::new(0, 0))
     }
+    /// How far through its timebox the job is, from 0.0 to 1.0.
+    pub fn progress(&self) -> Option<f32> {
+        self.progress_at(Utc::now())
+    }
+
+    fn progress_at(&self, now: DateTime<Utc>) -> Option<f32> {
+        let timebox = self.timebox?.as_secs_f32();
+        if timebox <= 0.0 {
+            return Some(1.0);
+        }
+        let elapsed = self.elapsed(now).num_milliseconds() as f32 / 1000.0;
+        Some((elapsed / timebox).clamp(0.0, 1.0))
+    }
+}
+
+/// Draws a fraction from 0.0 to 1.0 like `[####----] 50%`
+pub fn progress_bar(fraction: f32, width: usize) -> String {
+    let filled = (fraction * width as f32).round() as usize;
+    format!(
+        "[{}{}] {}%",
+        "#".repeat(filled),
+        "-".repeat(width.saturating_sub(filled)),
+        (fraction * 100.0).round()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeboxed(minutes: u64, begin_date: DateTime<Utc>) -> Job {
+        Job {
+            timebox: Some(StdDuration::from_secs(minutes * 60)),
+            ..Job::new("timeboxed".to_owned(), begin_date)
+        }
+    }
+
+    #[test]
+    fn progress_is_clamped_at_its_boundaries() {
+        let begin = Utc::now();
+        let job = timeboxed(10, begin);
+        let progress = |minutes| job.progress_at(begin + Duration::minutes(minutes));
+        assert_eq!(progress(-5), Some(0.0));
+        assert_eq!(progress(0), Some(0.0));
+        assert_eq!(progress(5), Some(0.5));
+        assert_eq!(progress(10), Some(1.0));
+        assert_eq!(progress(15), Some(1.0));
+
+        assert_eq!(timeboxed(0, begin).progress_at(begin), Some(1.0));
+        let untimed = Job {
+            timebox: None,
+            ..job.clone()
+        };
+        assert_eq!(untimed.progress_at(begin), None);
+    }
+
+    #[test]
+    fn progress_bar_fills_with_the_fraction() {
+        assert_eq!(progress_bar(0.0, 8), "[--------] 0%");
+        assert_eq!(progress_bar(0.5, 8), "[####----] 50%");
+        assert_eq!(progress_bar(1.0, 8), "[########] 100%");
+    }
+
+    #[test]
+    fn elapsed_stops_while_paused_and_is_never_negative() {
+        let begin = Utc::now();
+        let mut job = timeboxed(10, begin);
+        assert_eq!(job.elapsed(begin + Duration::minutes(7)), Duration::minutes(7));
+        assert_eq!(job.elapsed(begin - Duration::minutes(7)), Duration::zero());
+
+        job.paused_since = Some(begin + Duration::minutes(2));
+        assert_eq!(job.elapsed(begin + Duration::minutes(7)), Duration::minutes(2));
+        assert_eq!(job.elapsed(begin + Duration::minutes(1)), Duration::minutes(1));
+    }
 }
@@ -1,16 +1,27 @@
-use chrono::{serde::ts_seconds, DateTime, Duration, Local, Utc};
+use anyhow::Context;
+use chrono::{serde::ts_seconds, DateTime, Duration, Utc};
 
 use serde::{Deserialize, Serialize};
 use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
     fs::{self, OpenOptions},
+    io,
     path::Path,
+    str::FromStr,
+    time::Duration as StdDuration,
 };
 
 extern crate clap;
 
 use std::default::Default;
 
-use crate::{default, Job, StringMatch};
+use crate::{
+    default,
+    job::{new_id, ReminderPolicy},
+    settings,
+    Job, StringMatch,
+};
 
 type JobStack = Vec<Job>;
 
@@ -22,7 +33,88 @@ pub struct SuspendedStack {
     #[serde(with = "ts_seconds")]
     pub date_suspended: DateTime<Utc>,
     pub timer: Option<DateTime<Utc>>,
-    pub last_notifiaction: Option<DateTime<Utc>>,
+    // Files older than schema version 1 spell this field `last_notifiaction`
+    #[serde(alias = "last_notifiaction")]
+    pub last_notification: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "crate::job::is_zero")]
+    pub notification_count: u32,
+    #[serde(default, skip_serializing_if = "crate::job::is_false")]
+    pub pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminder_policy: Option<ReminderPolicy>,
+    /// The id of a job that must finish before this stack is ready.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waiting_on: Option<String>,
+    /// Resume this stack as soon as `waiting_on` finishes.
+    #[serde(default, skip_serializing_if = "crate::job::is_false")]
+    pub auto_resume: bool,
+    /// Someday/maybe stacks always sort after every other stack.
+    #[serde(default, skip_serializing_if = "crate::job::is_false")]
+    pub someday: bool,
+    /// Silent stacks keep their timer for ordering, but never send reminders.
+    #[serde(default, skip_serializing_if = "crate::job::is_false")]
+    pub silent: bool,
+}
+
+/// Describes how a job should be suspended.
+#[derive(Clone, Default)]
+pub struct SuspendOptions {
+    pub reason: String,
+    pub timer: Option<DateTime<Utc>>,
+    pub reminder_policy: Option<ReminderPolicy>,
+    pub waiting_on: Option<String>,
+    pub auto_resume: bool,
+    pub someday: bool,
+    pub silent: bool,
+}
+
+impl SuspendOptions {
+    pub fn into_stack(self, data: JobStack) -> SuspendedStack {
+        SuspendedStack {
+            data,
+            reason: self.reason,
+            date_suspended: Utc::now(),
+            timer: self.timer,
+            last_notification: None,
+            notification_count: 0,
+            pinned: false,
+            reminder_policy: self.reminder_policy,
+            waiting_on: self.waiting_on,
+            auto_resume: self.auto_resume,
+            someday: self.someday,
+            silent: self.silent,
+        }
+    }
+}
+
+impl SuspendedStack {
+    /// How long the stack had been suspended by `now`, never negative.
+    pub fn age(&self, now: DateTime<Utc>) -> Duration {
+        now.signed_duration_since(self.date_suspended).max(Duration::zero())
+    }
+
+    // Stops waiting on another job. Stacks that won't resume on their own become ready.
+    fn release(&mut self, now: DateTime<Utc>) {
+        self.waiting_on = None;
+        if !self.auto_resume {
+            self.timer = Some(now);
+            self.last_notification = None;
+            self.notification_count = 0;
+        }
+    }
+
+    // A stack is identified by the job at its root
+    pub fn id(&self) -> &str {
+        &self.data[0].id
+    }
+
+    /// Whether the stack can be picked back up: its timer has run out, if it has one,
+    /// and it isn't waiting on another job or filed under someday.
+    pub fn is_ready(&self, now: DateTime<Utc>) -> bool {
+        !self.someday
+            && self.waiting_on.is_none()
+            && self.timer.is_none_or(|timer| timer <= now)
+    }
 }
 
 
@@ -30,7 +122,9 @@ pub struct SuspendedStack {
 pub enum WorkState {
     Off,
     Working,
-    SlackingSince(DateTime<Utc>)
+    SlackingSince(DateTime<Utc>),
+    /// Slack alarms are held off until the break ends, then work mode carries on.
+    BreakUntil(DateTime<Utc>),
 }
 
 impl Default for WorkState {
@@ -41,16 +135,149 @@ impl Default for WorkState {
 
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub struct JobBoard {
+    /// The version of the jobs.ron format this board was saved with.
+    #[serde(default)]
+    pub schema_version: u32,
     pub work_state: WorkState,
+    /// Set when work mode was started outside of work hours.
+    #[serde(default, skip_serializing_if = "crate::job::is_false")]
+    pub work_forced: bool,
+    /// Set while work mode is on, but held off because it's outside work hours.
+    #[serde(default, skip_serializing_if = "crate::job::is_false")]
+    pub off_hours: bool,
+    /// A tag applied to every new job until it's changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// When a command other than the notifier last ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_interaction: Option<DateTime<Utc>>,
     pub active_stack: JobStack,
     pub suspended_stacks: Vec<SuspendedStack>,  
 }
 
+// Shortens `label` to `max_width` characters, marking the cut with an ellipsis
+fn truncate_label(label: &str, max_width: usize) -> String {
+    let mut truncated: String = label.chars().take(max_width).collect();
+    if label.chars().count() > max_width && max_width > 0 {
+        truncated.pop();
+        truncated.push('…');
+    }
+    truncated
+}
+
+// Formats a duration like a clock, e.g. 1:05:09, so durations line up in columns
+fn clock_duration(duration: Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Which stack `wyd mv` moves a job to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveTarget {
+    Active,
+    Suspended,
+}
+
+impl FromStr for MoveTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "active" => Ok(MoveTarget::Active),
+            "suspended" => Ok(MoveTarget::Suspended),
+            _ => anyhow::bail!("Unknown stack \"{}\". Expected active or suspended", s),
+        }
+    }
+}
+
+/// Orders for listing suspended stacks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StackSort {
+    /// Soonest timer first, the order stacks are stored in.
+    Timer,
+    /// Most recently suspended first.
+    Suspended,
+    /// Alphabetical by the root job's label.
+    Label,
+    /// Oldest root job first.
+    Age,
+    /// Highest priority root job first.
+    Priority,
+}
+
+impl FromStr for StackSort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "timer" => Ok(StackSort::Timer),
+            "suspended" => Ok(StackSort::Suspended),
+            "label" => Ok(StackSort::Label),
+            "age" => Ok(StackSort::Age),
+            "priority" => Ok(StackSort::Priority),
+            _ => anyhow::bail!(
+                "Unknown sort \"{}\". Expected one of: timer, suspended, label, age, priority",
+                s
+            ),
+        }
+    }
+}
+
+impl StackSort {
+    fn compare(self, stack1: &SuspendedStack, stack2: &SuspendedStack) -> Ordering {
+        // Pinned stacks stay on top whatever the order
+        let by_key = match self {
+            StackSort::Timer => Ordering::Equal,
+            StackSort::Suspended => stack2.date_suspended.cmp(&stack1.date_suspended),
+            StackSort::Label => stack1.data[0]
+                .label
+                .to_lowercase()
+                .cmp(&stack2.data[0].label.to_lowercase()),
+            StackSort::Age => stack1.data[0].begin_date.cmp(&stack2.data[0].begin_date),
+            StackSort::Priority => stack2.data[0].priority.cmp(&stack1.data[0].priority),
+        };
+        stack2.pinned.cmp(&stack1.pinned).then(by_key)
+    }
+}
+
+/// The current version of the jobs.ron format. Bump this and add a step
+/// to `JobBoard::migrate` whenever an older file needs upgrading.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// The start of a wyd HTML page, up to and including the opening `<body>` tag.
+pub fn html_header(title: &str, refresh_seconds: Option<u32>) -> String {
+    let refresh = match refresh_seconds {
+        Some(seconds) => format!(r#"<meta http-equiv="refresh" content="{}">"#, seconds),
+        None => String::new(),
+    };
+    format!(
+        r##"
+            <!doctype html>
+            <html lang=en>
+            <head>
+            <link rel=icon href=wyd-icon.png type="image/png">
+            <meta charset=utf-8>
+            {refresh}
+            <title>{title}</title>
+            <link rel="stylesheet" href="wyd-homepage.css" />
+            </head>
+            <body>
+            "##,
+        refresh = refresh,
+        title = title
+    )
+}
+
 impl JobBoard {
     #[allow(dead_code)]
     fn empty() -> Self {
         JobBoard {
+            schema_version: SCHEMA_VERSION,
             work_state: WorkState::Off,
+            work_forced: false,
+            off_hours: false,
+            context: None,
+            last_interaction: None,
             active_stack: default(),
             suspended_stacks: default(),
         }
@@ -68,70 +295,171 @@ impl JobBoard {
         let contents =
             fs::read_to_string(&stack_file_path).expect(&bad_path("Failed to read file {}"));
         if contents.is_empty() {
-            default()
+            JobBoard {
+                schema_version: SCHEMA_VERSION,
+                ..default()
+            }
         } else {
             ron::from_str(&contents).expect(&bad_path("Stack file at {} is malformed."))
         }
     }
 
-    fn find_job(&self, mut predicate: impl StringMatch) -> Option<(usize, &Job)> {
-        for (index, job) in self.active_stack.iter().enumerate() {
-            if predicate(&job.label) {
-                return Some((index, job));
+    /// Reads jobs.ron without opening it for writing or creating it.
+    pub fn load_read_only(app_dir: &Path) -> anyhow::Result<Self> {
+        let stack_file_path = app_dir.join("jobs.ron");
+        let contents = match fs::read_to_string(&stack_file_path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("Failed to read file {:?}", stack_file_path))
+            }
+        };
+        if contents.is_empty() {
+            return Ok(JobBoard {
+                schema_version: SCHEMA_VERSION,
+                ..default()
+            });
+        }
+        ron::from_str(&contents)
+            .with_context(|| format!("Stack file at {:?} is malformed.", stack_file_path))
+    }
+
+    /// Upgrades a board loaded from an older version of jobs.ron,
+    /// returning the version it started at if anything changed.
+    pub fn migrate(&mut self) -> Option<u32> {
+        let original_version = self.schema_version;
+        if original_version >= SCHEMA_VERSION {
+            return None;
+        }
+        // Version 1: jobs got ids, which serde made up while loading.
+        // The `last_notifiaction` typo is handled by a serde alias.
+        // Saving once is enough to make both permanent.
+        if original_version < 2 {
+            // Version 2: jobs and stacks depend on a job's id rather than its label.
+            // Labels can repeat, so the first job with the label is picked.
+            let mut ids = HashMap::new();
+            for job in self.all_jobs() {
+                ids.entry(job.label.clone()).or_insert_with(|| job.id.clone());
+            }
+            for job in self.all_jobs_mut() {
+                job.blocked_by = job.blocked_by.take().and_then(|label| ids.get(&label).cloned());
+            }
+            for stack in &mut self.suspended_stacks {
+                if let Some(label) = stack.waiting_on.take() {
+                    match ids.get(&label) {
+                        Some(id) => stack.waiting_on = Some(id.clone()),
+                        None => stack.release(Utc::now()),
+                    }
+                }
             }
         }
-        None
+        self.schema_version = SCHEMA_VERSION;
+        Some(original_version)
     }
 
-    pub fn suspend_current(
-        &mut self,
-        reason: String,
-        timer: Option<DateTime<Utc>>,
-    ) -> Result<(), ()> {
-        self.suspend_at(self.active_stack.len() - 1, reason, timer)
+    /// Unblocks jobs, and releases stacks, whose dependency is no longer on the board,
+    /// e.g. because it was moved to another board, so they aren't left waiting forever.
+    pub fn forget_missing_dependencies(&mut self) {
+        let ids: HashSet<String> = self.all_jobs().map(|job| job.id.clone()).collect();
+        for job in self.all_jobs_mut() {
+            if job.blocked_by.as_ref().is_some_and(|id| !ids.contains(id)) {
+                job.blocked_by = None;
+            }
+        }
+        let now = Utc::now();
+        for stack in &mut self.suspended_stacks {
+            if stack.waiting_on.as_ref().is_some_and(|id| !ids.contains(id)) {
+                stack.release(now);
+            }
+        }
+        self.sort_suspended_stacks();
     }
 
-    fn suspend_at(
-        &mut self,
-        index: usize,
-        reason: String,
-        timer: Option<DateTime<Utc>>,
-    ) -> Result<(), ()> {
-        if index >= self.active_stack.len() {
-            return Err(());
+    // Indices of every label sharing the best match score, in order.
+    fn best_matches<'a>(
+        labels: impl Iterator<Item = &'a str>,
+        mut pattern: impl StringMatch,
+    ) -> Vec<usize> {
+        let mut best_score = None;
+        let mut matches = Vec::new();
+        for (index, label) in labels.enumerate() {
+            if let Some(score) = pattern(label) {
+                match best_score {
+                    Some(best) if score < best => {}
+                    Some(best) if score == best => matches.push(index),
+                    _ => {
+                        best_score = Some(score);
+                        matches = vec![index];
+                    }
+                }
+            }
         }
-        let jobs_to_suspend = self.active_stack.split_off(index);
-        let suspended_stack = SuspendedStack {
-            data: jobs_to_suspend,
-            reason,
-            date_suspended: Utc::now(),
-            timer,
-            last_notifiaction: None,
-        };
-        self.add_suspended_stack(suspended_stack);
-        Ok(())
+        matches
     }
 
-    pub fn suspend_matching(
-        &mut self,
-        pattern: impl StringMatch,
-        reason: String,
-        timer: Option<DateTime<Utc>>,
-    ) -> Result<(), ()> {
-        if let Some((i, _job)) = self.find_job(pattern) {
-            self.suspend_at(i, reason, timer)
+    pub fn find_job_by_id(&self, id: &str) -> Option<usize> {
+        self.active_stack.iter().position(|job| job.id == id)
+    }
+
+    pub fn find_suspended_stack_by_id(&self, id: &str) -> Option<usize> {
+        self.suspended_stacks
+            .iter()
+            .position(|stack| stack.id() == id)
+    }
+
+    /// The index of the most (or least) recently suspended stack.
+    pub fn stack_by_suspension_date(&self, newest: bool) -> Option<usize> {
+        let dates = self.suspended_stacks.iter().map(|stack| stack.date_suspended).enumerate();
+        let (index, _date) = if newest {
+            dates.max_by_key(|(_, date)| *date)
         } else {
-            Err(())
+            dates.min_by_key(|(_, date)| *date)
+        }?;
+        Some(index)
+    }
+
+    pub fn matching_jobs(&self, pattern: impl StringMatch) -> Vec<usize> {
+        let labels = self.active_stack.iter().map(|job| job.label.as_str());
+        Self::best_matches(labels, pattern)
+    }
+
+    pub fn matching_suspended_stacks(&self, pattern: impl StringMatch) -> Vec<usize> {
+        let labels = self
+            .suspended_stacks
+            .iter()
+            .map(|stack| stack.data[0].label.as_str());
+        Self::best_matches(labels, pattern)
+    }
+
+    pub fn suspend_current(&mut self, options: SuspendOptions) -> Result<(), ()> {
+        match self.active_stack.len().checked_sub(1) {
+            Some(top_index) => self.suspend_at(top_index, options),
+            None => Err(()),
         }
     }
 
+    pub fn suspend_at(&mut self, index: usize, options: SuspendOptions) -> Result<(), ()> {
+        if index >= self.active_stack.len() {
+            return Err(());
+        }
+        let jobs_to_suspend = self.active_stack.split_off(index);
+        self.add_suspended_stack(options.into_stack(jobs_to_suspend));
+        Ok(())
+    }
+
     // todo - private
     pub fn sort_suspended_stacks(&mut self) {
         let now = Utc::now();
         self.suspended_stacks.sort_by(|stack1, stack2| {
             let timer1 = stack1.timer.unwrap_or(now);
             let timer2 = stack2.timer.unwrap_or(now);
-            timer1.cmp(&timer2)
+            // Pinned stacks float to the top, and someday stacks sink to the bottom
+            stack2
+                .pinned
+                .cmp(&stack1.pinned)
+                .then(stack1.someday.cmp(&stack2.someday))
+                .then(timer1.cmp(&timer2))
         })
     }
 
@@ -141,30 +469,128 @@ impl JobBoard {
         self.sort_suspended_stacks();
     }
 
-    pub fn resume_matching(&mut self, mut pattern: impl StringMatch) -> Result<(), ()> {
-        let mut found_index = self.suspended_stacks.len();
-        for (i, stack) in self.suspended_stacks.iter().enumerate() {
-            if pattern(&stack.data[0].label) {
-                found_index = i;
-                break;
-            }
-        }
-        self.resume_at_index(found_index)
+    /// The indices of every ready suspended stack, least urgent first. Resuming them
+    /// in this order leaves the most urgent on top, and since the indices count down,
+    /// resuming one doesn't move the ones still to come.
+    pub fn ready_stack_indices(&self, now: DateTime<Utc>) -> Vec<usize> {
+        (0..self.suspended_stacks.len())
+            .rev()
+            .filter(|index| self.suspended_stacks[*index].is_ready(now))
+            .collect()
     }
 
-    pub fn resume_at_index(&mut self, index: usize) -> Result<(), ()> {
+    /// Moves a suspended stack back onto the active stack. Unless `keep_times` is set,
+    /// the jobs' begin dates are shifted forward by the time spent suspended, so their
+    /// elapsed times and relative order survive the round trip.
+    pub fn resume_at_index(&mut self, index: usize, keep_times: bool) -> Result<(), ()> {
         if index >= self.suspended_stacks.len() {
             Err(())
         } else {
             let mut suspended_stack = self.suspended_stacks.remove(index);
+            let time_suspended = suspended_stack.age(Utc::now());
             for mut job in &mut suspended_stack.data {
-                job.begin_date = Utc::now();
+                if !keep_times {
+                    job.begin_date = job.begin_date + time_suspended;
+                    // Or starting the job later would discount the suspension twice
+                    job.paused_since = job.paused_since.map(|paused| paused + time_suspended);
+                }
+                job.last_notification = None;
+                job.notification_count = 0;
             }
             self.active_stack.extend(suspended_stack.data);
             Ok(())
         }
     }
 
+    /// Pushes a fresh copy of a suspended stack onto the active stack,
+    /// leaving the original suspended.
+    pub fn resume_copy_at_index(&mut self, index: usize) -> Result<(), ()> {
+        let stack = self.suspended_stacks.get(index).ok_or(())?;
+        let now = Utc::now();
+        let copies: Vec<Job> = stack
+            .data
+            .iter()
+            .map(|job| Job {
+                id: new_id(),
+                begin_date: now,
+                last_notification: None,
+                notification_count: 0,
+                paused_since: job.paused_since.map(|_| now),
+                ..job.clone()
+            })
+            .collect();
+        self.active_stack.extend(copies);
+        Ok(())
+    }
+
+    // Every job on the board, active or suspended
+    pub fn all_jobs(&self) -> impl Iterator<Item = &Job> {
+        self.active_stack
+            .iter()
+            .chain(self.suspended_stacks.iter().flat_map(|stack| &stack.data))
+    }
+
+    pub fn all_jobs_mut(&mut self) -> impl Iterator<Item = &mut Job> {
+        self.active_stack.iter_mut().chain(
+            self.suspended_stacks
+                .iter_mut()
+                .flat_map(|stack| &mut stack.data),
+        )
+    }
+
+    // Replaces a tag on every job and on the context, returning how many jobs
+    // changed and whether the context did.
+    pub fn rename_tag(&mut self, old: &str, new: &str) -> (usize, bool) {
+        let mut updated = 0;
+        for job in self.all_jobs_mut() {
+            if !job.tags.iter().any(|tag| tag == old) {
+                continue;
+            }
+            if job.tags.iter().any(|tag| tag == new) {
+                job.tags.retain(|tag| tag != old);
+            } else {
+                for tag in job.tags.iter_mut().filter(|tag| *tag == old) {
+                    *tag = new.to_owned();
+                }
+            }
+            updated += 1;
+        }
+        let context_renamed = self.context.as_deref() == Some(old);
+        if context_renamed {
+            self.context = Some(new.to_owned());
+        }
+        (updated, context_renamed)
+    }
+
+    // Releases suspended stacks waiting on a finished job, returning their ids.
+    // Stacks that won't resume on their own become ready right away.
+    pub fn release_waiting_stacks(&mut self, finished_id: &str) -> Vec<String> {
+        let mut released = Vec::new();
+        let now = Utc::now();
+        for stack in &mut self.suspended_stacks {
+            if stack.waiting_on.as_deref() != Some(finished_id) {
+                continue;
+            }
+            stack.release(now);
+            released.push(stack.id().to_owned());
+        }
+        self.sort_suspended_stacks();
+        released
+    }
+
+    // Clears the blocked flag of jobs waiting on a finished job,
+    // returning their labels.
+    pub fn unblock_dependents(&mut self, finished_id: &str) -> Vec<String> {
+        let mut unblocked = Vec::new();
+        for job in self.all_jobs_mut() {
+            if job.blocked_by.as_deref() == Some(finished_id) {
+                job.blocked_by = None;
+                unblocked.push(job.label.clone());
+            }
+        }
+        unblocked
+    }
+
     pub fn push(&mut self, job: Job) {
         self.active_stack.push(job);
     }
@@ -173,6 +599,17 @@ impl JobBoard {
         self.active_stack.pop()
     }
 
+    /// The timebox time left across every active job that has one, counting
+    /// expired timeboxes as nothing left, along with how many jobs have one.
+    pub fn timebox_remaining_total(&self) -> (StdDuration, usize) {
+        self.active_stack
+            .iter()
+            .filter_map(Job::timebox_remaining)
+            .fold((StdDuration::ZERO, 0), |(total, count), remaining| {
+                (total + remaining, count + 1)
+            })
+    }
+
     fn num_active_jobs(&self) -> usize {
         self.active_stack.len()
     }
@@ -189,26 +626,174 @@ impl JobBoard {
         }
     }
 
+    // The number of active jobs, `!` if any timebox expired, and the top label.
+    pub fn prompt_summary(&self, max_width: usize) -> String {
+        let top = match self.active_stack.last() {
+            Some(job) => job,
+            None => return String::new(),
+        };
+        let expired = self.active_stack.iter().any(|job| job.timebox_expired());
+        let label = truncate_label(&top.label, max_width);
+        let summary = format!(
+            "{}{} {}",
+            self.active_stack.len(),
+            if expired { "!" } else { "" },
+            label
+        );
+        summary.trim_end().to_owned()
+    }
+
+    /// A table of the active jobs, for `wyd info --wide`.
+    pub fn get_wide_summary(&self, note_counts: &HashMap<String, usize>) -> String {
+        if self.num_active_jobs() == 0 {
+            return self.empty_stack_message();
+        }
+        let now = Utc::now();
+        let mut output = format!(
+            "{:<6}  {:<30}  {:>9}  {:>9}  {:>5}  {}\n",
+            "ID", "LABEL", "ELAPSED", "TIMEBOX", "NOTES", "TAGS"
+        );
+        for job in &self.active_stack {
+            let elapsed = clock_duration(job.elapsed(now));
+            let timebox = match job.timebox_expiry() {
+                _ if job.timebox_expired() => "expired".to_owned(),
+                Some(expiry) => clock_duration(expiry.signed_duration_since(now)),
+                None => "-".to_owned(),
+            };
+            let tags: Vec<String> = job.tags.iter().map(|tag| format!("@{}", tag)).collect();
+            let line = format!(
+                "{:<6}  {:<30}  {:>9}  {:>9}  {:>5}  {}",
+                job.id,
+                truncate_label(&job.label, 30),
+                elapsed,
+                timebox,
+                note_counts.get(&job.id).copied().unwrap_or(0),
+                tags.join(" ")
+            );
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+        output
+    }
+
+    pub fn get_summary_since(&self, since: DateTime<Utc>) -> String {
+        if self.num_active_jobs() == 0 {
+            return self.empty_stack_message();
+        }
+        let (recent, older): (Vec<&Job>, Vec<&Job>) = self
+            .active_stack
+            .iter()
+            .partition(|job| job.begin_date >= since);
+        let mut output = String::new();
+        if !older.is_empty() {
+            output.push_str(&format!("({} older tasks hidden)\n", older.len()));
+        }
+        for job in recent {
+            output.push_str(&format!("{}\n", job));
+        }
+        output
+    }
+
+    pub fn get_tree_summary(&self) -> String {
+        if self.num_active_jobs() == 0 {
+            return self.empty_stack_message();
+        }
+        let now = Utc::now();
+        let mut output = String::new();
+        for (depth, job) in self.active_stack.iter().enumerate() {
+            // The bottom job is the root; each job above it is
+            // a subtask of the one below.
+            if depth > 0 {
+                output.push_str(&"    ".repeat(depth - 1));
+                output.push_str("└── ");
+            }
+            if job.timebox_expired() {
+                output.push_str("(!) ");
+            }
+            output.push_str(&job.label);
+            output.push_str(" #");
+            output.push_str(&job.id);
+
+            let elapsed = job.elapsed(now).to_std().unwrap_or_default();
+            let elapsed = StdDuration::from_secs(elapsed.as_secs());
+            output.push_str(&format!(
+                " [elapsed: {}",
+                humantime::format_duration(elapsed)
+            ));
+            if let Some(timebox) = job.timebox {
+                output.push_str(&format!(
+                    " | timebox: {}",
+                    humantime::format_duration(timebox)
+                ));
+            }
+            output.push_str("]\n");
+        }
+        output
+    }
+
     // todo - private
     pub fn suspended_stack_summary(&self) -> String {
+        self.sorted_stack_summary(StackSort::Timer, false, None)
+    }
+
+    // Summarizes the someday stacks, or every other stack, in the given order,
+    // stopping after `limit` stacks. Sorting only affects the summary; stored
+    // order is always by timer.
+    pub fn sorted_stack_summary(
+        &self,
+        sort: StackSort,
+        someday: bool,
+        limit: Option<usize>,
+    ) -> String {
+        let mut stacks: Vec<&SuspendedStack> = self
+            .suspended_stacks
+            .iter()
+            .filter(|stack| stack.someday == someday)
+            .collect();
+        stacks.sort_by(|stack1, stack2| sort.compare(stack1, stack2));
+        let limit = limit.unwrap_or(stacks.len()).min(stacks.len());
+        let hidden = stacks.len() - limit;
+        let mut output = self.summarize_stacks(stacks.into_iter().take(limit));
+        if hidden > 0 {
+            output.push_str(&format!("(+{} more, use --all)", hidden));
+        }
+        output
+    }
+
+    fn summarize_stacks<'a>(&self, stacks: impl Iterator<Item = &'a SuspendedStack>) -> String {
         let mut output = String::new();
-        for stack in &self.suspended_stacks {
+        for stack in stacks {
             for (i, job) in stack.data.iter().enumerate() {
                 if i == 0 {
+                    if stack.pinned {
+                        output.push_str("📌 ");
+                    }
                     if let Some(timer) = stack.timer {
-                        let local_time = DateTime::<Local>::from(timer);
+                        let local_time = settings::display_time(timer);
                         output.push_str(&format!("{}", local_time.format("%a %F %r")));
                         output.push_str(":  ");
                         output.push_str(&job.label);
+                        if stack.silent {
+                            output.push_str(" (silent)");
+                        }
                     } else {
                         output.push_str(&job.label);
                         output.push_str(" (suspended at ");
                         output.push_str(&format!(
                             "{}",
-                            DateTime::<Local>::from(stack.date_suspended).format("%a %F %r")
+                            settings::display_time(stack.date_suspended).format("%a %F %r")
                         ));
                         output.push_str(")");
                     }
+                    if let Some(waiting_on) = &stack.waiting_on {
+                        let label = match self.all_jobs().find(|job| &job.id == waiting_on) {
+                            Some(job) => job.label.as_str(),
+                            None => waiting_on.as_str(),
+                        };
+                        output.push_str(&format!(" (waiting on \"{}\")", label));
+                    }
+                    output.push_str(" #");
+                    output.push_str(stack.id());
                 } else {
                     output.push_str("    ");
                     output.push_str(&job.label);
@@ -233,8 +818,9 @@ impl JobBoard {
             }
         };
 
-        // Consider the closest upcoming suspended task.
-        match self.suspended_stacks.first() {
+        // Consider the closest upcoming suspended task. Someday
+        // tasks are never due.
+        match self.suspended_stacks.iter().find(|stack| !stack.someday) {
             // If there are no suspended tasks, none are due
             None => false,
             Some(task) => match task.timer {
@@ -262,19 +848,9 @@ impl JobBoard {
         self.sort_suspended_stacks();
         // Should replace with a real templating engine later.
         let emojis = self.pick_emojis();
-        let mut output = String::new();
+        let mut output = html_header("How's it going?", Some(30));
         output += &format!(
             r##"
-            <!doctype html>
-            <html lang=en>
-            <head>
-            <link rel=icon href=wyd-icon.png type="image/png">
-            <meta charset=utf-8>
-            <meta http-equiv="refresh" content="30">
-            <title>How's it going?</title>
-            <link rel="stylesheet" href="wyd-homepage.css" />
-            </head>
-            <body>
             <h1>{emoji_a}{emoji_b} Hello from Wyd {emoji_b}{emoji_a}</h1>
             "##,
             emoji_a = emojis.0,
@@ -344,3 +920,233 @@ impl JobBoard {
         output
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::Priority;
+
+    fn job(label: &str) -> Job {
+        Job::new(label.to_owned(), Utc::now())
+    }
+
+    fn suspended(label: &str) -> SuspendedStack {
+        SuspendOptions::default().into_stack(vec![job(label)])
+    }
+
+    // Lists the root labels of the board's ready stacks in `sort` order
+    fn sorted_labels(board: &JobBoard, sort: StackSort) -> Vec<String> {
+        let mut stacks: Vec<&SuspendedStack> = board.suspended_stacks.iter().collect();
+        stacks.sort_by(|stack1, stack2| sort.compare(stack1, stack2));
+        stacks.iter().map(|stack| stack.data[0].label.clone()).collect()
+    }
+
+    #[test]
+    fn each_sort_key_orders_stacks() {
+        let now = Utc::now();
+        let mut board = JobBoard::default();
+        for (label, minutes, priority) in [
+            ("beta", 1, Priority::High),
+            ("alpha", 2, Priority::Low),
+            ("gamma", 3, Priority::Normal),
+        ] {
+            let mut stack = suspended(label);
+            stack.timer = Some(now + Duration::minutes(minutes));
+            stack.date_suspended = now - Duration::minutes(minutes);
+            stack.data[0].begin_date = now - Duration::hours(4 - minutes);
+            stack.data[0].priority = priority;
+            board.add_suspended_stack(stack);
+        }
+        assert_eq!(sorted_labels(&board, StackSort::Timer), ["beta", "alpha", "gamma"]);
+        assert_eq!(sorted_labels(&board, StackSort::Suspended), ["beta", "alpha", "gamma"]);
+        assert_eq!(sorted_labels(&board, StackSort::Label), ["alpha", "beta", "gamma"]);
+        assert_eq!(sorted_labels(&board, StackSort::Age), ["beta", "alpha", "gamma"]);
+        assert_eq!(sorted_labels(&board, StackSort::Priority), ["beta", "gamma", "alpha"]);
+    }
+
+    #[test]
+    fn fuzzy_matches_what_substrings_miss() {
+        let mut board = JobBoard::default();
+        for label in ["deploy to production", "deploy staging", "write docs"] {
+            board.add_suspended_stack(suspended(label));
+        }
+        let labels = |matches: Vec<usize>| -> Vec<&str> {
+            let mut labels: Vec<&str> = matches
+                .into_iter()
+                .map(|index| board.suspended_stacks[index].data[0].label.as_str())
+                .collect();
+            labels.sort_unstable();
+            labels
+        };
+        let substring =
+            |pattern| labels(board.matching_suspended_stacks(crate::substring_matcher(pattern)));
+        let fuzzy =
+            |pattern| labels(board.matching_suspended_stacks(crate::fuzzy_matcher(pattern)));
+
+        assert!(substring("deploy prod").is_empty());
+        assert_eq!(fuzzy("deploy prod"), ["deploy to production"]);
+        assert_eq!(substring("deploy"), ["deploy staging", "deploy to production"]);
+        assert_eq!(fuzzy("deploy"), ["deploy staging", "deploy to production"]);
+        assert_eq!(fuzzy("wd"), ["write docs"]);
+        assert!(substring("wd").is_empty());
+        assert!(fuzzy("xyz").is_empty());
+    }
+
+    #[test]
+    fn pinned_stacks_lead_every_sort() {
+        let mut board = JobBoard::default();
+        board.add_suspended_stack(suspended("apple"));
+        let mut pinned = suspended("zebra");
+        pinned.pinned = true;
+        board.add_suspended_stack(pinned);
+        for sort in [
+            StackSort::Timer,
+            StackSort::Suspended,
+            StackSort::Label,
+            StackSort::Age,
+            StackSort::Priority,
+        ] {
+            let summary = board.sorted_stack_summary(sort, false, None);
+            assert!(
+                summary.find("zebra") < summary.find("apple"),
+                "{:?} put a pinned stack second",
+                sort
+            );
+        }
+    }
+
+    #[test]
+    fn resuming_keeps_jobs_spaced_apart() {
+        let now = Utc::now();
+        let mut stack = suspended("root");
+        stack.data.push(job("child"));
+        stack.data[0].begin_date = now - Duration::hours(3);
+        stack.data[1].begin_date = now - Duration::hours(2);
+        stack.date_suspended = now - Duration::hours(1);
+        let mut board = JobBoard::default();
+        board.add_suspended_stack(stack.clone());
+        board.add_suspended_stack(stack);
+
+        board.resume_at_index(0, false).unwrap();
+        let (root, child) = (&board.active_stack[0], &board.active_stack[1]);
+        assert_eq!(child.begin_date - root.begin_date, Duration::hours(1));
+        // Only the time spent suspended drops out of the root's elapsed time
+        let elapsed = root.elapsed(Utc::now());
+        let expected = Duration::hours(2);
+        assert!(elapsed >= expected && elapsed < expected + Duration::minutes(1));
+
+        board.active_stack.clear();
+        board.resume_at_index(0, true).unwrap();
+        assert_eq!(board.active_stack[0].begin_date, now - Duration::hours(3));
+        assert_eq!(board.active_stack[1].begin_date, now - Duration::hours(2));
+    }
+
+    #[test]
+    fn age_counts_from_suspension_and_is_never_negative() {
+        let now = Utc::now();
+        let mut stack = suspended("waiting");
+        stack.date_suspended = now - Duration::minutes(90);
+        assert_eq!(stack.age(now), Duration::minutes(90));
+        stack.date_suspended = now + Duration::minutes(5);
+        assert_eq!(stack.age(now), Duration::zero());
+    }
+
+    #[test]
+    fn remaining_total_counts_expired_timeboxes_as_nothing() {
+        let now = Utc::now();
+        let mut board = JobBoard::default();
+        for (minutes, started_ago) in [(Some(30), 0), (Some(10), 20), (None, 5), (Some(60), 15)] {
+            let mut job = job("timeboxed");
+            job.timebox = minutes.map(|minutes| StdDuration::from_secs(minutes * 60));
+            job.begin_date = now - Duration::minutes(started_ago);
+            board.push(job);
+        }
+        let (total, count) = board.timebox_remaining_total();
+        assert_eq!(count, 3);
+        let expected = StdDuration::from_secs(75 * 60);
+        assert!(total <= expected && total > expected - StdDuration::from_secs(5));
+
+        assert_eq!(JobBoard::default().timebox_remaining_total(), (StdDuration::ZERO, 0));
+    }
+
+    #[test]
+    fn dependencies_follow_ids_not_labels() {
+        let mut board = JobBoard::default();
+        let (first_review, second_review) = (job("review"), job("review"));
+        let mut waiting = job("merge");
+        waiting.blocked_by = Some(first_review.id.clone());
+        let second_id = second_review.id.clone();
+        board.push(first_review);
+        board.push(second_review);
+        board.add_suspended_stack(SuspendOptions::default().into_stack(vec![waiting]));
+
+        assert!(board.unblock_dependents(&second_id).is_empty());
+        board.forget_missing_dependencies();
+        assert!(board.suspended_stacks[0].data[0].blocked_by.is_some());
+        board.active_stack.remove(0);
+        board.forget_missing_dependencies();
+        assert_eq!(board.suspended_stacks[0].data[0].blocked_by, None);
+    }
+
+    #[test]
+    fn old_boards_depend_on_the_first_job_with_the_label() {
+        let mut board = JobBoard::default();
+        let (first_review, second_review) = (job("review"), job("review"));
+        let first_id = first_review.id.clone();
+        let mut waiting = job("merge");
+        waiting.blocked_by = Some("review".to_owned());
+        let mut orphan = job("deploy");
+        orphan.blocked_by = Some("long gone".to_owned());
+        for job in [first_review, second_review, waiting, orphan] {
+            board.push(job);
+        }
+
+        assert_eq!(board.migrate(), Some(0));
+        assert_eq!(board.active_stack[2].blocked_by, Some(first_id));
+        assert_eq!(board.active_stack[3].blocked_by, None);
+    }
+
+    #[test]
+    fn waiting_stacks_follow_ids_not_labels() {
+        let mut board = JobBoard::default();
+        let (first_review, second_review) = (job("review"), job("review"));
+        let (first_id, second_id) = (first_review.id.clone(), second_review.id.clone());
+        board.push(first_review);
+        board.push(second_review);
+        for label in ["merge", "release"] {
+            let options = SuspendOptions {
+                waiting_on: Some(first_id.clone()),
+                auto_resume: label == "release",
+                ..SuspendOptions::default()
+            };
+            board.add_suspended_stack(options.into_stack(vec![job(label)]));
+        }
+
+        assert!(board.release_waiting_stacks(&second_id).is_empty());
+        assert_eq!(board.release_waiting_stacks(&first_id).len(), 2);
+        assert!(board.suspended_stacks.iter().all(|stack| stack.waiting_on.is_none()));
+
+        let mut waiting = suspended("deploy");
+        waiting.waiting_on = Some("gone".to_owned());
+        board.add_suspended_stack(waiting);
+        board.forget_missing_dependencies();
+        assert!(board.suspended_stacks.iter().all(|stack| stack.waiting_on.is_none()));
+    }
+
+    #[test]
+    fn renaming_a_tag_renames_the_context() {
+        let mut board = JobBoard::default();
+        let mut tagged = job("review");
+        tagged.tags = vec!["wip".to_owned(), "code".to_owned()];
+        board.push(tagged);
+        board.push(job("lunch"));
+        board.context = Some("wip".to_owned());
+
+        assert_eq!(board.rename_tag("wip", "in-progress"), (1, true));
+        assert_eq!(board.active_stack[0].tags, ["in-progress", "code"]);
+        assert_eq!(board.context.as_deref(), Some("in-progress"));
+        assert_eq!(board.rename_tag("code", "in-progress"), (1, false));
+        assert_eq!(board.active_stack[0].tags, ["in-progress"]);
+        assert_eq!(board.rename_tag("wip", "done"), (0, false));
+    }
+}
@@ -1,7 +1,7 @@
-use chrono::{DateTime, Duration, Local, Utc};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
 use chrono_english::Dialect;
 
-use std::{fmt::Display, fs::{self, OpenOptions}, io::Write, thread, time::Duration as StdDuration};
+use std::{fmt::Display, fs::{self, OpenOptions}, io::{self, Read, Write}, path::{Path, PathBuf}, thread, time::Duration as StdDuration};
 
 extern crate clap;
 use clap::{crate_version, AppSettings, ArgSettings, Parser};
@@ -9,16 +9,35 @@ use clap::{crate_version, AppSettings, ArgSettings, Parser};
 use std::default::Default;
 
 mod job;
-use job::Job;
+use job::{is_valid_tag, parse_tag, Job, Priority, ReminderPolicy};
 
 mod job_board;
 
+mod export;
+use export::{ExportFormat, HistoryFormat};
+
+mod settings;
+
+mod history;
+
+mod import;
+
+mod stats;
+use stats::ReportFormat;
+
+mod goals;
+use goals::GoalKind;
+
+mod replay;
+
 mod wyd_application;
-use wyd_application::WydApplication;
+use wyd_application::{StateLock, WydApplication};
 
 use anyhow::Context;
+use url::Url;
 
-use crate::job_board::WorkState;
+use crate::job_board::{JobBoard, MoveTarget, StackSort, SuspendOptions, WorkState};
+use crate::wyd_application::{DoneOptions, PushOptions, ResumeOptions};
 
 fn default<D: Default>() -> D {
     Default::default()
@@ -29,9 +48,20 @@ impl Display for Job {
         if self.timebox_expired() {
             f.write_str("(!) ")?;
         }
+        // Dependencies that left the board are forgotten when it's loaded
+        if self.blocked_by.is_some() {
+            f.write_str("(blocked) ")?;
+        }
+        if self.paused_since.is_some() {
+            f.write_str("(paused) ")?;
+        }
         f.write_str(&self.label)?;
+        for tag in &self.tags {
+            f.write_str(" @")?;
+            f.write_str(tag)?;
+        }
         f.write_str(" | started at ")?;
-        let local_time = DateTime::<Local>::from(self.begin_date);
+        let local_time = settings::display_time(self.begin_date);
         let formatted_date = local_time.format("%r");
         formatted_date.fmt(f)?;
         let chrono_timebox = match self.timebox {
@@ -42,7 +72,7 @@ impl Display for Job {
             None => None,
         };
         if let Some(chrono_timebox) = chrono_timebox {
-            let time_elapsed = Local::now().signed_duration_since(self.begin_date);
+            let time_elapsed = self.elapsed(Utc::now());
             let time_remaining = chrono_timebox - time_elapsed;
             if let Ok(std_dur) = time_remaining.to_std() {
                 f.write_str(" | timebox remaining : ")?;
@@ -52,17 +82,97 @@ impl Display for Job {
             } else {
                 f.write_str(" | timebox expired")?;
             }
+            if let Some(progress) = self.progress() {
+                f.write_str(" ")?;
+                f.write_str(&job::progress_bar(progress, 10))?;
+            }
+        }
+        match self.priority {
+            Priority::High => f.write_str(" | high priority")?,
+            Priority::Low => f.write_str(" | low priority")?,
+            Priority::Normal => {}
+        }
+        if let Some(estimate) = self.estimate {
+            f.write_str(" | estimate: ")?;
+            humantime::format_duration(estimate).fmt(f)?;
+        }
+        if let Some(quiet_until) = self.quiet_until {
+            if quiet_until > Utc::now() {
+                let local_time = settings::display_time(quiet_until);
+                write!(f, " | quiet until {}", local_time.format("%r"))?;
+            }
+        }
+        for link in &self.links {
+            // The alternate form is used in the HTML dashboard
+            if f.alternate() {
+                write!(f, r#" | <a href="{0}">{0}</a>"#, link)?;
+            } else {
+                write!(f, " | {}", link)?;
+            }
         }
+        f.write_str(" | #")?;
+        f.write_str(&self.id)?;
         Ok(())
     }
 }
 
-pub trait StringMatch: FnMut(&str) -> bool {}
+/// Scores how well a label matches. `None` means no match;
+/// otherwise higher scores are better matches.
+pub trait StringMatch: FnMut(&str) -> Option<i64> {}
+
+impl<T> StringMatch for T where T: FnMut(&str) -> Option<i64> {}
+
+fn substring_matcher(pattern: &str) -> impl Fn(&str) -> Option<i64> + '_ {
+    move |s: &str| -> Option<i64> {
+        if s.contains(pattern) {
+            Some(0)
+        } else {
+            None
+        }
+    }
+}
+
+fn fuzzy_matcher(pattern: &str) -> impl Fn(&str) -> Option<i64> + '_ {
+    move |s: &str| -> Option<i64> { fuzzy_score(pattern, s) }
+}
+
+// Matches if the pattern's characters appear in order in the candidate,
+// ignoring case and whitespace. Runs of consecutive characters and
+// characters at the start of a word earn a higher score.
+fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_index = None;
+    for pattern_char in pattern.to_lowercase().chars() {
+        if pattern_char.is_whitespace() {
+            continue;
+        }
+        let index = position
+            + candidate[position..]
+                .iter()
+                .position(|&c| c == pattern_char)?;
+        score += 1;
+        if index > 0 && previous_index == Some(index - 1) {
+            score += 5;
+        }
+        if index == 0 || !candidate[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous_index = Some(index);
+        position = index + 1;
+    }
+    Some(score)
+}
 
-impl<T> StringMatch for T where T: FnMut(&str) -> bool {}
+fn parse_date(input: &str) -> anyhow::Result<DateTime<Utc>> {
+    let date = chrono_english::parse_date_string(input, Local::now(), Dialect::Us)?;
+    Ok(date.with_timezone(&Utc))
+}
 
-fn substring_matcher(pattern: &str) -> impl Fn(&str) -> bool + '_ {
-    move |s: &str| -> bool { s.contains(pattern) }
+fn parse_day(input: &str) -> anyhow::Result<NaiveDate> {
+    let date = chrono_english::parse_date_string(input, Local::now(), Dialect::Us)?;
+    Ok(date.naive_local().date())
 }
 
 fn parse_date_or_dur(input: &str) -> anyhow::Result<StdDuration> {
@@ -72,6 +182,25 @@ fn parse_date_or_dur(input: &str) -> anyhow::Result<StdDuration> {
     Ok(dur.to_std()?)
 }
 
+// Accepts either how long ago the job started ("30m") or when it started ("2pm")
+fn parse_retro(input: &str) -> anyhow::Result<StdDuration> {
+    parse_retro_at(input, Local::now())
+}
+
+fn parse_retro_at(input: &str, now: DateTime<Local>) -> anyhow::Result<StdDuration> {
+    if let Ok(dur) = humantime::parse_duration(input) {
+        return Ok(dur);
+    }
+    let start = chrono_english::parse_date_string(input, now, Dialect::Us)?;
+    match now.signed_duration_since(start).to_std() {
+        Ok(dur) => Ok(dur),
+        Err(_) => anyhow::bail!(
+            "Start time {} is in the future. --retro can only move the start backward.",
+            start.format("%r")
+        ),
+    }
+}
+
 #[derive(Parser, Debug)]
 //     let matches = App::new("What You're Doing")
 //         .version(crate_version!())
@@ -84,12 +213,47 @@ enum Command {
         #[clap(parse(try_from_str = humantime::parse_duration))]
         timebox: Option<StdDuration>,
 
-        /// "Start" a job some time in the past
+        /// "Start" a job some time in the past, either a duration ago (30m) or at a time (2pm)
         #[clap(long, short)]
-        #[clap(parse(try_from_str = humantime::parse_duration))]
+        #[clap(parse(try_from_str = parse_retro))]
         retro: Option<StdDuration>,
 
-        /// Name of the new task. Supports bare words like `wyd push Send emails`
+        /// Marks the new task as blocked until a matching task is finished.
+        #[clap(long)]
+        depends_on: Option<String>,
+
+        /// How often to remind you once the timebox expires: once, backoff, or an interval like 5m
+        #[clap(long)]
+        reminder: Option<ReminderPolicy>,
+
+        /// Holds back reminders for the task until this time (e.g. "2pm")
+        #[clap(long)]
+        #[clap(parse(try_from_str = parse_date))]
+        quiet_until: Option<DateTime<Utc>>,
+
+        /// How much the task matters: low, normal, or high. Low priority busywork
+        /// doesn't count as focus in work mode.
+        #[clap(long, default_value = "normal")]
+        priority: Priority,
+
+        /// Queues the task without starting its clock or reminders, until `wyd start`.
+        #[clap(long)]
+        start_paused: bool,
+
+        /// A reference link for the task, like a ticket or pull request. Can be repeated.
+        #[clap(long, multiple_occurrences = true, multiple_values = false)]
+        link: Vec<Url>,
+
+        /// Sets the timebox to how long similar tasks took, going by the history of finished tasks.
+        #[clap(long)]
+        estimate_from_history: bool,
+
+        /// Pushes each argument as its own step, with the first step on top.
+        #[clap(long)]
+        checklist: bool,
+
+        /// Name of the new task. Supports bare words like `wyd push Send emails`.
+        /// Words like `@errands` are stored as tags instead.
         words: Vec<String>,
     },
 
@@ -99,6 +263,23 @@ enum Command {
         words: Vec<String>,
     },
 
+    /// Pushes a new task with the same name and tags as the last one finished.
+    Repeat {
+        /// Also gives it the same timebox as the last one.
+        #[clap(long, short)]
+        timebox: bool,
+    },
+
+    /// Records a task you did without pushing it, as if it just finished, e.g. `log-work 45m call with Sam`
+    LogWork {
+        /// How long the task took.
+        #[clap(parse(try_from_str = humantime::parse_duration))]
+        duration: StdDuration,
+
+        /// Name of the task, along with any @tags.
+        words: Vec<String>,
+    },
+
     /// Moves a task from the active stack to the suspended queue.
     Suspend {
         /// Sets a timer, after which the suspended task will send reminders.
@@ -106,15 +287,57 @@ enum Command {
         #[clap(parse(try_from_str = parse_date_or_dur))]
         timebox: Option<StdDuration>,
 
+        /// How often to remind you once the timer expires: once, backoff, or an interval like 5m
+        #[clap(long)]
+        reminder: Option<ReminderPolicy>,
+
+        /// Waits for a matching task to finish, instead of (or as well as) a timer.
+        #[clap(long)]
+        until_done: Option<String>,
+
+        /// Resumes the task automatically once the --until-done task finishes.
+        #[clap(long)]
+        auto: bool,
+
+        /// Files the task under "someday", below every other suspended task.
+        #[clap(long)]
+        someday: bool,
+
+        /// Keeps the --timebox for ordering, but never sends reminders for it.
+        #[clap(long)]
+        silent: bool,
+
         /// Creates a new suspended task instead of suspending an existing one.
         #[clap(long, short)]
         new: bool,
 
         /// An optional note about why you suspended the task.
-        #[clap(long, short, default_value = "None")]
-        reason: String,
+        #[clap(long, short)]
+        reason: Option<String>,
+
+        /// Fills in the reason from where you are: the current git branch, or else
+        /// the current directory. An explicit --reason wins.
+        #[clap(long)]
+        auto_reason: bool,
 
-        /// The name (or part of the name) of the task to be suspended.
+        /// Changes the reason a matching suspended task was suspended to --reason, instead.
+        #[clap(long, value_name = "PATTERN")]
+        edit_reason: Option<String>,
+
+        /// Matches the task name fuzzily instead of by substring.
+        #[clap(long)]
+        fuzzy: bool,
+
+        /// Picks the first match instead of asking when several tasks match.
+        #[clap(long)]
+        first: bool,
+
+        /// Moves the task to the board in another data directory (as given to --app-dir),
+        /// creating it if needed.
+        #[clap(long, value_name = "DIR")]
+        to: Option<PathBuf>,
+
+        /// The name (or part of the name, or `#id`) of the task to be suspended.
         words: Vec<String>,
     },
 
@@ -123,6 +346,64 @@ enum Command {
         /// Marks the task as cancelled instead of complete
         #[clap(long, short)]
         cancelled: bool,
+
+        /// Completes this many tasks from the top of the stack at once.
+        #[clap(long, short = 'n', default_value = "1")]
+        count: usize,
+
+        /// Prints how long the task took on a line of its own, like a stopwatch.
+        #[clap(long)]
+        time: bool,
+
+        /// Prints how long the task took as JSON, e.g. `{"elapsed_secs": 90, ...}`
+        #[clap(long)]
+        json: bool,
+
+        /// Puts the most recently finished task back on the stack.
+        #[clap(long)]
+        undo: bool,
+
+        /// Credits the time spent to this tag in stats, instead of the task's own tags.
+        #[clap(long)]
+        tag_time: Option<String>,
+
+        /// The `#id` of the top task, to guard against completing the wrong one.
+        id: Option<String>,
+    },
+
+    /// Starts the clock of a task pushed with --start-paused
+    #[clap(alias = "unpause")]
+    Start {
+        /// Picks the first match instead of asking when several tasks match.
+        #[clap(long)]
+        first: bool,
+
+        /// The name (or part of the name, or `#id`) of the paused task. Defaults to the topmost one.
+        words: Vec<String>,
+    },
+
+    /// Cancels the top task, or a matching active task and the subtasks above it
+    Cancel {
+        /// Why the task was cancelled, kept in the log and the history.
+        #[clap(long, short)]
+        reason: Option<String>,
+
+        /// Matches the task name fuzzily instead of by substring.
+        #[clap(long)]
+        fuzzy: bool,
+
+        /// Picks the first match instead of asking when several tasks match.
+        #[clap(long)]
+        first: bool,
+
+        /// The name (or part of the name, or `#id`) of the task to cancel.
+        words: Vec<String>,
+    },
+
+    /// Shows progress towards today's goals, or sets them
+    Goal {
+        #[clap(subcommand)]
+        action: Option<GoalAction>,
     },
 
     /// Output reminders for expired timers
@@ -132,14 +413,114 @@ enum Command {
         force: bool,
     },
 
+    /// Sounds a reminder for the current task right now, e.g. to check the alarm works.
+    Nudge,
+
     /// Resumes a suspended task.
-    Resume { words: Vec<String> },
+    ///
+    /// Resumed tasks go on top of the active stack, so they become subtasks of
+    /// whatever you were doing. With --stack-into-current that's checked first:
+    /// the resume fails if the current task can't take subtasks.
+    Resume {
+        /// Matches the task name fuzzily instead of by substring.
+        #[clap(long)]
+        fuzzy: bool,
+
+        /// Picks the first match instead of asking when several tasks match.
+        #[clap(long)]
+        first: bool,
+
+        /// Keeps the tasks' original start times instead of discounting the time spent suspended.
+        #[clap(long)]
+        keep_times: bool,
+
+        /// Resumes a copy of the task, leaving the original suspended (e.g. for recurring chores).
+        #[clap(long)]
+        keep_suspended: bool,
+
+        /// Resumes the most recently suspended task.
+        #[clap(long)]
+        newest: bool,
+
+        /// Resumes the least recently suspended task.
+        #[clap(long)]
+        oldest: bool,
+
+        /// Undoes the most recent suspend, as if it never happened.
+        /// Short for --newest --keep-times.
+        #[clap(long)]
+        last: bool,
+
+        /// Resumes the task as a subtask of the current one, failing if that task has a timebox.
+        #[clap(long)]
+        stack_into_current: bool,
+
+        /// Gives the resumed task a fresh timebox, as if by `wyd timebox` (e.g. 25m)
+        #[clap(long)]
+        #[clap(parse(try_from_str = humantime::parse_duration))]
+        and_timebox: Option<StdDuration>,
+
+        /// Picks the task interactively, with fzf if it's installed. Any name given
+        /// starts the search. Falls back to the usual matching without a terminal.
+        #[clap(long, alias = "fzf")]
+        pick: bool,
+
+        /// With --keep-times or --last, lengthens timeboxes by the time spent suspended,
+        /// so tasks get back the time they had left. A plain resume does this already.
+        #[clap(long)]
+        timebox_remaining: bool,
+
+        /// Resumes every suspended task that's ready: its timer has run out, or it
+        /// has none, and it isn't waiting on another task or filed under someday.
+        #[clap(long)]
+        all_ready: bool,
+
+        /// Prints what --all-ready would resume, without changing anything.
+        #[clap(long)]
+        dry_run: bool,
+
+        /// The name (or part of the name, or `#id`) of the task to be resumed.
+        words: Vec<String>,
+    },
 
     /// Prints the active task stack.
-    Info,
+    Info {
+        /// Shows the stack as a tree of tasks and their subtasks.
+        #[clap(long)]
+        tree: bool,
+
+        /// Shows the stack as a table of ids, times, note counts and tags.
+        #[clap(long)]
+        wide: bool,
+
+        /// Only shows tasks started after this time (e.g. "2pm" or "yesterday")
+        #[clap(long)]
+        #[clap(parse(try_from_str = parse_date))]
+        since: Option<DateTime<Utc>>,
+
+        /// Only shows the total time left on active tasks' timeboxes.
+        #[clap(long)]
+        remaining_total: bool,
+    },
 
     /// Prints a list of all tasks, including suspended ones.
-    Ls,
+    Ls {
+        /// Prints both stacks as JSON, for editor integrations.
+        #[clap(long)]
+        json: bool,
+
+        /// Orders suspended tasks by timer, suspended, label, age, or priority.
+        #[clap(long, default_value = "timer")]
+        sort: StackSort,
+
+        /// Only shows the first N suspended tasks.
+        #[clap(long, short = 'n')]
+        limit: Option<usize>,
+
+        /// Shows every suspended task, overriding --limit.
+        #[clap(long)]
+        all: bool,
+    },
 
     /// Starts the notifier process, which sends wyd's reminder notifications.
     Notifier {
@@ -160,10 +541,62 @@ enum Command {
         /// Removes the current timebox instead of applying a new one.
         #[clap(long, short)]
         remove: bool,
+
+        /// Lists the expiry of every timeboxed job in the active stack.
+        #[clap(long)]
+        show_all: bool,
+
+        /// Measures the timebox from when the task started, so a long-running
+        /// task may already be expired.
+        #[clap(long)]
+        from_start: bool,
+
+        /// Measures the timebox from now, restarting the task's clock. This is the default.
+        #[clap(long)]
+        from_now: bool,
+    },
+
+    /// Records how long you expect the current task to take (e.g. 45m)
+    Estimate {
+        /// The estimated duration.
+        #[clap(parse(try_from_str = humantime::parse_duration))]
+        estimate: StdDuration,
     },
 
+    /// Redraws the job board whenever jobs.ron changes, e.g. on a second screen
+    Watch,
+
     /// Prints today's log file
-    Log,
+    Log {
+        /// Keeps printing new log lines as they're written, like `tail -f`.
+        #[clap(long, short)]
+        follow: bool,
+
+        /// Empties today's log, keeping a copy in a .bak file.
+        #[clap(long)]
+        clear: bool,
+
+        /// Clears the log without asking for confirmation.
+        #[clap(long, short)]
+        yes: bool,
+
+        /// Only prints lines written since the current task started.
+        #[clap(long)]
+        current: bool,
+
+        /// Sums up the day in a few bullet points instead: tasks, time, and notes.
+        #[clap(long)]
+        summarize: bool,
+
+        /// The day to --summarize (e.g. "yesterday"). Defaults to today.
+        #[clap(long)]
+        #[clap(parse(try_from_str = parse_day))]
+        date: Option<NaiveDate>,
+
+        /// Prints the --summarize bullets as text or markdown.
+        #[clap(long)]
+        format: Option<ReportFormat>,
+    },
 
     /// Starts a countdown for mindfulness
     Meditate {
@@ -177,185 +610,1689 @@ enum Command {
 
     /// Adds a message to today's log
     Jot {
+        /// Files the note under a tag, so `wyd search --tag` can find it later.
+        #[clap(long, short)]
+        tag: Option<String>,
+
+        /// Reopens the last note in $EDITOR to fix it, instead of writing a new one.
+        #[clap(long, short)]
+        edit: bool,
+
         /// List of words forming the content of the message.
         words: Vec<String>,
     },
 
+    /// Searches jotted notes from every day
+    Search {
+        /// Only shows notes with this tag.
+        #[clap(long, short)]
+        tag: Option<String>,
+
+        /// Only shows notes containing these words.
+        words: Vec<String>,
+    },
+
     /// Enters work mode (sends reminders every few minutes if no timebox is set.)
     Work {
         /// Exits work mode
         #[clap(long, short)]
         done: bool,
-    }
-}
 
-#[derive(Parser, Debug)]
-#[clap(name = "What You're Doing")]
-#[clap(version = crate_version!())]
-#[clap(setting = AppSettings::InferSubcommands)]
-struct Arguments {
-    #[clap(subcommand)]
-    subcommand: Option<Command>,
-}
+        /// Prints whether work mode is on, and how long you've been slacking or on a break.
+        #[clap(long, short)]
+        status: bool,
 
-fn main() {
-    match perform_work() {
-        Ok(()) => {
-            // Done
-        },
-        Err(error) => {
-            handle_error(error)
-        }
-    }
-}
+        /// Takes a break from work mode for this long (e.g. 15m), without slack alarms.
+        #[clap(long = "break", value_name = "DURATION")]
+        #[clap(parse(try_from_str = humantime::parse_duration))]
+        break_for: Option<StdDuration>,
+    },
 
-fn perform_work() -> anyhow::Result<()> {
-    let args = Arguments::parse();
+    /// Exports suspended task timers in another format (e.g. an iCalendar feed)
+    Export {
+        /// The output format: ical for suspended tasks' timers, csv for finished
+        /// tasks, or json or ron for both.
+        #[clap(long, short, default_value = "ical")]
+        format: ExportFormat,
 
-    let app_dir = dirs::data_local_dir()
-        .context("Could not locate current user's app data folder.")?
-        .join(".wyd");
+        /// Writes the export to a file instead of printing it.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
 
-    fs::create_dir_all(&app_dir).context("Could not create application directory")?;
-    let mut app = WydApplication::load(app_dir).context("Failed to load application state from app directory.")?;
+        /// Also exports active and suspended tasks started in range, with json or ron.
+        #[clap(long)]
+        include_board: bool,
 
-    let subcommand = args.subcommand.unwrap_or(Command::Info);
-    use Command::*;
-    match subcommand {
-        Push {
-            timebox,
-            retro,
-            words,
-        } => {
-            let label = words.join(" ");
-            if label.is_empty() {
-                eprintln!("Can't create a job without a label.");
-                return Ok(());
-            }
-            app.create_job(label, timebox, retro)?;
-        }
+        /// Only exports what happened after this time (e.g. "last monday")
+        #[clap(long)]
+        #[clap(parse(try_from_str = parse_date))]
+        since: Option<DateTime<Utc>>,
 
-        FiveMinutes { words } => {
-            app.create_job(words.join(" "), Some(StdDuration::from_secs(5 * 60)), None)?;
-        }
+        /// Only exports what happened before this time.
+        #[clap(long)]
+        #[clap(parse(try_from_str = parse_date))]
+        until: Option<DateTime<Utc>>,
 
-        Suspend {
-            words,
-            reason,
-            timebox,
-            new,
-        } => {
-            let words = words.join(" ");
-            let timer = if let Some(std_duration) = timebox {
-                let utc_date = Utc::now()
-                    + Duration::from_std(std_duration)
-                        .expect("Unable to convert std duration to chrono duration.");
-                Some(utc_date)
-            } else {
-                None
-            };
+        /// Replaces labels, reasons, tags and links with placeholders like `task-1`,
+        /// keeping the times and structure, e.g. to share in a bug report.
+        #[clap(long)]
+        anonymize: bool,
+    },
 
-            if new {
-                app.create_suspended_job(words, reason, timer);
-            } else if words.is_empty() {
-                app.suspend_current_job(reason, timer);
-            } else {
-                app.suspend_job_named(&words, reason, timer);
-            }
-            app.save().context("Unable to save after attempting to suspend job.")?;
-        }
+    /// Reschedules a suspended task's reminder without resuming it.
+    Snooze {
+        /// When to be reminded next (e.g. "30m" or "tomorrow at 9am")
+        #[clap(long, short)]
+        #[clap(parse(try_from_str = parse_date_or_dur))]
+        until: StdDuration,
 
-        Done { cancelled } => {
-            app.complete_current_job(cancelled)?;
-        }
+        /// The name (or part of the name, or `#id`) of the suspended task.
+        words: Vec<String>,
+    },
 
-        Resume { words } => {
-            let pattern = words.join(" ");
-            app.resume_job_named(&pattern)?;
-        }
+    /// Keeps a suspended task at the top of the suspended list.
+    Pin {
+        /// The name (or part of the name, or `#id`) of the suspended task.
+        words: Vec<String>,
+    },
 
-        Notifier { kill, become_id } => {
-            if kill {
-                app.kill_notifier();
-            } else if let Some(id_str) = become_id {
-                app.become_notifier(&id_str).context("Unable to start notifier process")?;
-            } else {
-                app.spawn_notifier();
-            }
-        }
+    /// Undoes `pin` for a suspended task.
+    Unpin {
+        /// The name (or part of the name, or `#id`) of the suspended task.
+        words: Vec<String>,
+    },
 
-        Remind { force: _ } => {
-            app.update_timers()?;
-            app.save().context("Unable to save after attempting to update timers.")?;
-        }
+    /// Prints statistics about your work
+    Stats {
+        /// Shows time spent focused versus slacking in work mode.
+        #[clap(long)]
+        focus: bool,
 
-        Ls => {
-            app.ls_job_board();
-        }
+        /// Shows how many days in a row you've finished at least one task.
+        #[clap(long)]
+        streak: bool,
 
-        Info => {
-            print!("{}", app.get_summary());
-        }
+        /// Shows time spent on finished tasks per tag.
+        #[clap(long)]
+        tags: bool,
 
-        Timebox { timebox, remove } => {
-            if timebox.is_some() && remove {
-                eprintln!("Cannot specify a new timebox while using the --remove flag.");
-            } else if timebox.is_none() && !remove {
-                app.print_current_timebox();
-            } else {
-                app.apply_timebox(timebox)?;
-            }
-        }
+        /// Sums up today so far: tasks finished, time focused, and the current task.
+        #[clap(long)]
+        now: bool,
 
-        Log => {
-            app.print_log();
-        }
+        /// Shows a calendar of how many tasks you finished each day.
+        #[clap(long)]
+        heatmap: bool,
 
-        Meditate { seconds, intent } => {
-            for i in 0..seconds {
-                println!("{}", seconds - i);
-                thread::sleep(StdDuration::from_secs(1));
-            }
-            if let Some(intent) = intent {
-                println!("{}", intent);
-            }
-        }
+        /// How many weeks the --heatmap calendar covers (12 by default).
+        #[clap(long)]
+        weeks: Option<u32>,
 
-        Jot { words } => {
-            let content = words.join(" ");
-            app.add_log_note(content);
-        }
+        /// Charts how many tasks you've finished in each hour of the day.
+        #[clap(long)]
+        per_hour: bool,
 
-        Work { done } => {
-            let work_state = if done {
-                WorkState::Off
-            } else {
-                WorkState::Working
-            };
-            app.set_work_state(work_state)?;
-        }
-    };
+        /// Charts how many tasks you've finished on each day of the week.
+        #[clap(long)]
+        per_weekday: bool,
 
-    Ok(())
-}
+        /// Only charts the last this many days for --per-hour and --per-weekday.
+        #[clap(long)]
+        days: Option<u32>,
 
-fn handle_error(error: anyhow::Error) {
-    let app_dir = dirs::data_local_dir()
-        .context("Could not locate current user's app data folder.")
-        .unwrap()
-        .join(".wyd");
+        /// Shows how far estimates were off from the time tasks took, overall and per tag.
+        #[clap(long)]
+        estimate_accuracy: bool,
 
-    fs::create_dir_all(&app_dir)
-        .context("Could not create application directory")
-        .unwrap();
-    
-    let mut error_log_file = OpenOptions::new()
-        .write(true)
-        .append(true)
-        .open(app_dir.join("wyd-error.log"))
-        .unwrap();
+        /// Prints the --estimate-accuracy table as text or markdown.
+        #[clap(long)]
+        format: Option<ReportFormat>,
+
+        /// Exports the history of finished tasks instead, e.g. as csv
+        #[clap(long)]
+        export: Option<HistoryFormat>,
+
+        /// Writes the export to a file instead of printing it.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+
+        /// Only exports tasks finished after this time (e.g. "last monday")
+        #[clap(long)]
+        #[clap(parse(try_from_str = parse_date))]
+        since: Option<DateTime<Utc>>,
+
+        /// Only exports tasks finished before this time.
+        #[clap(long)]
+        #[clap(parse(try_from_str = parse_date))]
+        until: Option<DateTime<Utc>>,
+
+        /// The day to report on (e.g. "yesterday"). Defaults to today.
+        #[clap(long)]
+        #[clap(parse(try_from_str = parse_day))]
+        day: Option<NaiveDate>,
+    },
+
+    /// Replays a day's finished tasks as a timeline
+    Replay {
+        /// The day to replay (e.g. "yesterday"). Defaults to today.
+        #[clap(long)]
+        #[clap(parse(try_from_str = parse_day))]
+        day: Option<NaiveDate>,
 
-    writeln!(error_log_file, "{:#}", error)
-        .context("Error attempting to write to error log")
-        .unwrap();
+        /// Writes the timeline as an HTML Gantt chart instead of printing it.
+        #[clap(long)]
+        html: bool,
+
+        /// Where to write the HTML timeline. Defaults to the data directory.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// Exports tasks as a Chrome trace, which can be loaded from chrome://tracing
+    Timeline {
+        /// Only includes tasks finished after this time (e.g. "yesterday")
+        #[clap(long)]
+        #[clap(parse(try_from_str = parse_date))]
+        since: Option<DateTime<Utc>>,
+
+        /// Writes the trace to a file instead of printing it.
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// Moves the job with an exact `#id` to the active or suspended stack.
+    Mv {
+        /// The `#id` of the job to move.
+        id: String,
+
+        /// Where to move it: active or suspended.
+        to: MoveTarget,
+
+        /// An optional note about why you suspended the task.
+        #[clap(long, short, default_value = "None")]
+        reason: String,
+
+        /// Sets a timer when suspending, after which the task will send reminders.
+        #[clap(long, short)]
+        #[clap(parse(try_from_str = parse_date_or_dur))]
+        timebox: Option<StdDuration>,
+    },
+
+    /// Prints wyd's version.
+    Version {
+        /// Also prints the build, data directory, and notifier status, for bug reports.
+        #[clap(long)]
+        full: bool,
+    },
+
+    /// Runs wyd commands from a file (or stdin), one per line, saving once at the end.
+    Bulk {
+        /// File of commands, written without the leading `wyd`. Reads stdin if omitted or `-`.
+        file: Option<PathBuf>,
+
+        /// Keeps running the remaining commands after one fails.
+        #[clap(long, short)]
+        keep_going: bool,
+    },
+
+    /// Sets a context, which is added as a tag to every new task until changed
+    Context {
+        /// The new context. Prints the current context if omitted.
+        context: Option<String>,
+
+        /// Stops tagging new tasks with a context.
+        #[clap(long)]
+        clear: bool,
+    },
+
+    /// Renames a tag on every task (e.g. `wyd rename-tag @wip @in-progress`)
+    RenameTag {
+        /// The tag to rename.
+        old: String,
+
+        /// The tag's new name.
+        new: String,
+    },
+
+    /// Prints a one line summary for a shell prompt, e.g. `2! Write docs`
+    Prompt {
+        /// Truncates the top task's label to this many characters.
+        #[clap(long, default_value = "20")]
+        max_width: usize,
+    },
+
+    /// Replaces the job board with one read from a file (RON, or JSON for .json files)
+    Import {
+        /// The file to import.
+        file: PathBuf,
+
+        /// Imports without asking for confirmation.
+        #[clap(long, short)]
+        yes: bool,
+    },
+
+    /// Rewrites jobs.ron tidily, leaving out fields that hold their defaults
+    Compact {
+        /// Also drops finished jobs older than this from the completion history, e.g. 1year
+        #[clap(long, parse(try_from_str = humantime::parse_duration))]
+        prune_history: Option<StdDuration>,
+    },
+
+    /// Opens wyd's data directory in the file manager.
+    Open {
+        /// Opens the HTML dashboard in the browser instead.
+        #[clap(long)]
+        html: bool,
+
+        /// Opens the current task's links in the browser instead.
+        #[clap(long)]
+        links: bool,
+    },
+
+    /// Prints or changes a setting (e.g. `wyd config backup_count 50`)
+    Config {
+        /// The setting to print or change. Prints all settings if omitted.
+        key: Option<String>,
+
+        /// The new value for the setting.
+        value: Option<String>,
+    },
+}
+
+#[derive(Parser, Debug)]
+enum GoalAction {
+    /// Sets a goal for today, e.g. `tasks 5` or `focus 4h`
+    Set {
+        /// What the goal counts: tasks or focus
+        kind: GoalKind,
+
+        /// How many tasks to finish, or how long to focus for in work mode.
+        value: String,
+    },
+
+    /// Removes today's goals
+    Clear,
+}
+
+#[derive(Parser, Debug)]
+#[clap(name = "What You're Doing")]
+#[clap(version = crate_version!())]
+#[clap(setting = AppSettings::InferSubcommands)]
+struct Arguments {
+    /// Keeps wyd's data in this directory, instead of WYD_DATA_DIR or the default.
+    #[clap(long, global = true)]
+    app_dir: Option<PathBuf>,
+
+    /// Prints the full chain of causes of any error, instead of only logging it.
+    #[clap(long, global = true)]
+    debug: bool,
+
+    /// Doesn't count this run as using wyd for `auto_suspend_after`, e.g. for a
+    /// status bar that checks on wyd every few seconds.
+    #[clap(long, global = true)]
+    poll: bool,
+
+    #[clap(subcommand)]
+    subcommand: Option<Command>,
+}
+
+fn main() {
+    let args = Arguments::parse();
+    let app_dir_flag = args.app_dir.clone();
+    let debug = args.debug;
+    if debug && std::env::var_os("RUST_LIB_BACKTRACE").is_none() {
+        // Lets errors capture a backtrace where the toolchain supports it
+        std::env::set_var("RUST_LIB_BACKTRACE", "1");
+    }
+    match perform_work(args) {
+        Ok(()) => {
+            // Done
+        },
+        Err(error) => {
+            handle_error(error, app_dir_flag, debug)
+        }
+    }
+}
+
+fn perform_work(args: Arguments) -> anyhow::Result<()> {
+    let app_dir = app_dir(args.app_dir)?;
+
+    // Prompts are redrawn constantly, so skip everything but reading the jobs file
+    if let Some(Command::Prompt { max_width }) = args.subcommand {
+        print!("{}", JobBoard::load_read_only(&app_dir)?.prompt_summary(max_width));
+        return Ok(());
+    }
+    if let Some(Command::Version { full }) = args.subcommand {
+        return print_version(&app_dir, full);
+    }
+
+    let subcommand = args.subcommand.unwrap_or_else(default_command);
+    let mut app = load_app(app_dir, &subcommand)?;
+    if !args.poll && !matches!(subcommand, Command::Notifier { .. } | Command::Remind { .. }) {
+        app.record_interaction();
+    }
+    run_command(&mut app, subcommand)
+}
+
+// Loads the app for `subcommand`, holding the state lock unless it only reads the board
+fn load_app(app_dir: PathBuf, subcommand: &Command) -> anyhow::Result<WydApplication> {
+    if is_read_only(subcommand) {
+        return WydApplication::load_read_only(app_dir)
+            .context("Failed to load application state from app directory.");
+    }
+    // The notifier locks each update itself
+    let state_lock = match subcommand {
+        Command::Notifier { become_id: Some(_), .. } => None,
+        _ => Some(StateLock::acquire(&app_dir)?),
+    };
+    let mut app = WydApplication::load(app_dir)
+        .context("Failed to load application state from app directory.")?;
+    if let Some(state_lock) = state_lock {
+        app.hold_state_lock(state_lock);
+    }
+    Ok(app)
+}
+
+fn print_version(app_dir: &Path, full: bool) -> anyhow::Result<()> {
+    println!("wyd {}", crate_version!());
+    if !full {
+        return Ok(());
+    }
+    println!("Build: {}", env!("WYD_GIT_HASH"));
+    println!("Data directory: {}", app_dir.display());
+    // Shows the version on disk, since loading for real would migrate it.
+    // A broken board is what this is often run to investigate, so it's no reason to stop.
+    match JobBoard::load_read_only(app_dir) {
+        Ok(board) => println!(
+            "jobs.ron schema: {} (current: {})",
+            board.schema_version,
+            job_board::SCHEMA_VERSION
+        ),
+        Err(error) => println!("jobs.ron schema: unreadable ({:#})", error),
+    }
+    // The lock file holds the notifier's id, or "kill" once it's been told to stop
+    let notifier_running = match fs::read(app_dir.join(".notifier")) {
+        Ok(contents) => contents != b"kill",
+        Err(_) => false,
+    };
+    println!(
+        "Notifier: {}",
+        if notifier_running { "running" } else { "not running" }
+    );
+    Ok(())
+}
+
+// Commands that never change the job board, and can skip the full load
+fn is_read_only(subcommand: &Command) -> bool {
+    matches!(
+        subcommand,
+        Command::Info { .. }
+            | Command::Ls { .. }
+            | Command::Log { clear: false, .. }
+            | Command::Stats { now: true, .. }
+            | Command::Watch
+            | Command::Nudge
+            | Command::Resume { dry_run: true, .. }
+            | Command::Work { status: true, .. }
+            | Command::Meditate { .. }
+    )
+}
+
+// Describes where the user is working, for `suspend --auto-reason`
+fn context_reason() -> Option<String> {
+    let branch = std::process::Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned());
+    match branch {
+        // A detached HEAD has no branch to name
+        Some(branch) if !branch.is_empty() && branch != "HEAD" => {
+            Some(format!("Switched to branch {}", branch))
+        }
+        _ => {
+            let dir = std::env::current_dir().ok()?;
+            let name = dir.file_name()?.to_string_lossy().into_owned();
+            Some(format!("Working in {}", name))
+        }
+    }
+}
+
+// Joins the words of a label or pattern, so whitespace on its own counts as nothing
+fn join_words(words: &[String]) -> String {
+    words.join(" ").trim().to_owned()
+}
+
+// Running `wyd` on its own prints the active stack
+fn default_command() -> Command {
+    Command::Info {
+        tree: false,
+        wide: false,
+        since: None,
+        remaining_total: false,
+    }
+}
+
+fn run_command(app: &mut WydApplication, subcommand: Command) -> anyhow::Result<()> {
+    use Command::*;
+    match subcommand {
+        Push {
+            timebox,
+            retro,
+            depends_on,
+            reminder,
+            quiet_until,
+            priority,
+            start_paused,
+            link,
+            estimate_from_history,
+            checklist,
+            words,
+        } => {
+            if start_paused && retro.is_some() {
+                app.reject("Cannot use --retro with --start-paused, which hasn't started yet.");
+                return Ok(());
+            }
+            if estimate_from_history && (timebox.is_some() || checklist) {
+                app.reject("Cannot use --estimate-from-history with --timebox or --checklist.");
+                return Ok(());
+            }
+            if matches!(quiet_until, Some(quiet_until) if quiet_until <= Utc::now()) {
+                app.reject("The --quiet-until time has already passed.");
+                return Ok(());
+            }
+            let (tags, words): (Vec<_>, Vec<_>) =
+                words.into_iter().partition(|word| parse_tag(word).is_some());
+            if checklist && (timebox.is_some() || depends_on.is_some()) {
+                app.reject("Cannot use --timebox or --depends-on with --checklist.");
+                return Ok(());
+            }
+            if checklist && words.iter().any(|word| word.trim().is_empty()) {
+                app.reject("Can't create a checklist step without a label.");
+                return Ok(());
+            }
+            let mut label = join_words(&words);
+            let mut notes = Vec::new();
+            if label.is_empty() && !checklist {
+                if let Some((composed_label, composed_notes)) = app.compose_job()? {
+                    label = composed_label;
+                    notes = composed_notes;
+                }
+            }
+            if label.is_empty() {
+                app.reject("Can't create a job without a label.");
+                return Ok(());
+            }
+            let options = PushOptions {
+                timebox,
+                retro,
+                depends_on,
+                reminder_policy: reminder,
+                tags: tags.iter().map(|tag| tag[1..].to_owned()).collect(),
+                links: link,
+                quiet_until,
+                priority,
+                start_paused,
+                notes,
+            };
+            if checklist {
+                app.create_checklist(words, options)?;
+            } else {
+                app.create_job_from_history(label, options, estimate_from_history)?;
+            }
+        }
+
+        FiveMinutes { words } => {
+            let label = join_words(&words);
+            if label.is_empty() {
+                app.reject("Can't create a job without a label.");
+                return Ok(());
+            }
+            let options = PushOptions {
+                timebox: Some(StdDuration::from_secs(5 * 60)),
+                ..default()
+            };
+            app.create_job(label, options)?;
+        }
+
+        Repeat { timebox } => {
+            app.repeat_last_completion(timebox)?;
+        }
+
+        LogWork { duration, words } => {
+            let (tags, words): (Vec<_>, Vec<_>) =
+                words.into_iter().partition(|word| parse_tag(word).is_some());
+            let label = join_words(&words);
+            if label.is_empty() {
+                app.reject("Can't log work without a label.");
+            } else if duration.as_secs() == 0 {
+                app.reject("Can't log work that took no time.");
+            } else {
+                let tags = tags.iter().map(|tag| tag[1..].to_owned()).collect();
+                app.log_work(label, duration, tags)?;
+            }
+        }
+
+        Suspend {
+            words,
+            reason,
+            timebox,
+            reminder,
+            until_done,
+            auto,
+            someday,
+            silent,
+            new,
+            edit_reason,
+            fuzzy,
+            first,
+            to,
+            auto_reason,
+        } => {
+            let reason = match reason {
+                Some(reason) => reason,
+                None if auto_reason => context_reason().unwrap_or_else(|| "None".to_owned()),
+                None => "None".to_owned(),
+            };
+            if let Some(pattern) = edit_reason {
+                let other_options = to.is_some()
+                    || timebox.is_some()
+                    || reminder.is_some()
+                    || until_done.is_some()
+                    || auto
+                    || someday
+                    || silent
+                    || new
+                    || !words.is_empty();
+                if other_options {
+                    app.reject("--edit-reason only takes a new --reason.");
+                } else {
+                    app.edit_suspension_reason(&pattern, reason, fuzzy, first)?;
+                }
+                return Ok(());
+            }
+            if auto && until_done.is_none() {
+                app.reject("--auto needs a task to wait on with --until-done.");
+                return Ok(());
+            }
+            if to.is_some() && (new || until_done.is_some()) {
+                app.reject("Cannot use --to with --new or --until-done.");
+                return Ok(());
+            }
+            let words = join_words(&words);
+            if new && words.is_empty() {
+                app.reject("Can't create a suspended job without a label.");
+                return Ok(());
+            }
+            let waiting_on = match until_done {
+                Some(pattern) => match app.choose_job_id(&pattern, "wait on") {
+                    Some(id) => Some(id),
+                    None => return Ok(()),
+                },
+                None => None,
+            };
+            let timer = if let Some(std_duration) = timebox {
+                let utc_date = Utc::now()
+                    + Duration::from_std(std_duration)
+                        .expect("Unable to convert std duration to chrono duration.");
+                Some(utc_date)
+            } else {
+                None
+            };
+
+            let options = SuspendOptions {
+                reason,
+                timer,
+                reminder_policy: reminder,
+                waiting_on,
+                auto_resume: auto,
+                someday,
+                silent,
+            };
+            if let Some(target_dir) = to {
+                app.suspend_job_to(&words, options, fuzzy, first, &target_dir)?;
+                return Ok(());
+            }
+            if new {
+                app.create_suspended_job(words, options);
+            } else if words.is_empty() {
+                app.suspend_current_job(options);
+            } else {
+                app.suspend_job_named(&words, options, fuzzy, first);
+            }
+            app.save().context("Unable to save after attempting to suspend job.")?;
+        }
+
+        Done {
+            cancelled,
+            count,
+            time,
+            json,
+            undo,
+            tag_time,
+            id,
+        } => {
+            if undo {
+                if cancelled || time || json || count != 1 || id.is_some() || tag_time.is_some() {
+                    app.reject("--undo can't be combined with other options.");
+                } else {
+                    app.undo_completion()?;
+                }
+                return Ok(());
+            }
+            if count == 0 {
+                app.reject("The count must be at least 1.");
+                return Ok(());
+            }
+            let tag_time = tag_time.map(|tag| tag.trim_start_matches('@').to_owned());
+            if let Some(tag) = &tag_time {
+                if !is_valid_tag(tag) {
+                    app.reject(format!(
+                        "\"{}\" isn't a valid tag. Tags can't be empty or contain spaces.",
+                        tag
+                    ));
+                    return Ok(());
+                }
+            }
+            if let Some(id) = id {
+                if !app.is_current_job(&id) {
+                    app.reject(format!("The current job's id is not {}.", id));
+                    return Ok(());
+                }
+            }
+            let options = DoneOptions {
+                cancelled,
+                count,
+                show_time: time,
+                json,
+                time_tag: tag_time,
+                reason: None,
+            };
+            app.complete_current_jobs(options)?;
+        }
+
+        Goal { action } => match action {
+            Some(GoalAction::Set { kind, value }) => {
+                if let Err(error) = app.set_goal(kind, &value) {
+                    app.reject(format!("{:#}", error));
+                }
+            }
+            Some(GoalAction::Clear) => app.clear_goals()?,
+            None => app.print_goal_progress()?,
+        },
+
+        Start { first, words } => {
+            let pattern = join_words(&words);
+            let pattern = if pattern.is_empty() { None } else { Some(pattern.as_str()) };
+            app.start_job_named(pattern, first)?;
+        }
+
+        Cancel {
+            reason,
+            fuzzy,
+            first,
+            words,
+        } => {
+            let pattern = join_words(&words);
+            let pattern = if pattern.is_empty() { None } else { Some(pattern.as_str()) };
+            app.cancel_job_named(pattern, reason, fuzzy, first)?;
+        }
+
+        Resume {
+            words,
+            fuzzy,
+            first,
+            keep_times,
+            keep_suspended,
+            newest,
+            oldest,
+            stack_into_current,
+            and_timebox,
+            pick,
+            last,
+            all_ready,
+            dry_run,
+            timebox_remaining,
+        } => {
+            if dry_run && !all_ready {
+                app.reject("--dry-run only applies to --all-ready.");
+                return Ok(());
+            }
+            if all_ready {
+                let other_options = !words.is_empty()
+                    || fuzzy
+                    || first
+                    || keep_suspended
+                    || newest
+                    || oldest
+                    || last
+                    || stack_into_current
+                    || and_timebox.is_some()
+                    || timebox_remaining
+                    || pick;
+                if other_options {
+                    app.reject("--all-ready only takes --keep-times and --dry-run.");
+                } else {
+                    app.resume_all_ready(keep_times, dry_run)?;
+                }
+                return Ok(());
+            }
+            if last && (newest || oldest || keep_suspended) {
+                app.reject("Cannot use --last with --newest, --oldest or --keep-suspended.");
+                return Ok(());
+            }
+            let (newest, keep_times) = (newest || last, keep_times || last);
+            if timebox_remaining && !keep_times {
+                app.reject(
+                    "--timebox-remaining only applies with --keep-times or --last. \
+                    Otherwise resuming already keeps the time left on timeboxes."
+                );
+                return Ok(());
+            }
+            if timebox_remaining && and_timebox.is_some() {
+                app.reject("Cannot use --timebox-remaining with --and-timebox.");
+                return Ok(());
+            }
+            if pick && (newest || oldest) {
+                app.reject("Cannot use --pick with --newest, --oldest or --last.");
+                return Ok(());
+            }
+            if newest && oldest {
+                app.reject("Cannot use --newest and --oldest together.");
+                return Ok(());
+            }
+            if (newest || oldest) && !words.is_empty() {
+                app.reject("Cannot give a task name with --newest, --oldest or --last.");
+                return Ok(());
+            }
+            if keep_times && keep_suspended {
+                app.reject("Cannot use --keep-times with --keep-suspended, which starts a fresh copy.");
+                return Ok(());
+            }
+            let pattern = join_words(&words);
+            let options = ResumeOptions {
+                fuzzy,
+                first,
+                keep_times,
+                keep_suspended,
+                newest,
+                oldest,
+                stack_into_current,
+                timebox: and_timebox,
+                pick,
+                timebox_remaining,
+            };
+            app.resume_job_named(&pattern, options)?;
+        }
+
+        Notifier { kill, become_id } => {
+            if kill {
+                app.kill_notifier();
+            } else if let Some(id_str) = become_id {
+                app.clone()
+                    .become_notifier(&id_str)
+                    .context("Unable to start notifier process")?;
+            } else {
+                app.spawn_notifier();
+            }
+        }
+
+        Nudge => {
+            app.nudge();
+        }
+
+        Remind { force: _ } => {
+            app.update_timers()?;
+            app.save_timers().context("Unable to save after attempting to update timers.")?;
+        }
+
+        Ls {
+            json,
+            sort,
+            limit,
+            all,
+        } => {
+            if json {
+                app.print_json_listing()?;
+            } else {
+                app.ls_job_board(sort, if all { None } else { limit });
+            }
+        }
+
+        Info {
+            tree,
+            wide,
+            since,
+            remaining_total,
+        } => {
+            if remaining_total {
+                if tree || wide || since.is_some() {
+                    app.reject("Cannot use --remaining-total with other views or --since.");
+                } else {
+                    app.print_timebox_remaining_total();
+                }
+                return Ok(());
+            }
+            if let Some(context) = app.context() {
+                println!("Context: @{}", context);
+            }
+            if tree && wide {
+                app.reject("Cannot use --tree and --wide together.");
+            } else if (tree || wide) && since.is_some() {
+                app.reject("Cannot use --since with the --tree or --wide views.");
+            } else if wide {
+                print!("{}", app.get_wide_summary()?);
+            } else if let Some(since) = since {
+                print!("{}", app.get_summary_since(since));
+            } else if tree {
+                print!("{}", app.get_tree_summary());
+            } else {
+                let summary = app.get_summary();
+                print!("{}", summary);
+                if let Some(goal_summary) = app.goal_summary()? {
+                    // The empty stack message has no trailing newline
+                    if !summary.ends_with('\n') {
+                        println!();
+                    }
+                    println!("{}", goal_summary);
+                }
+            }
+        }
+
+        Timebox {
+            timebox,
+            remove,
+            show_all,
+            from_start,
+            from_now,
+        } => {
+            if timebox.is_some() && remove {
+                app.reject("Cannot specify a new timebox while using the --remove flag.");
+            } else if from_start && from_now {
+                app.reject("Cannot use --from-start and --from-now together.");
+            } else if (from_start || from_now) && timebox.is_none() {
+                app.reject("--from-start and --from-now need a new timebox to apply.");
+            } else if show_all && (timebox.is_some() || remove) {
+                app.reject("Cannot change the timebox while using the --show-all flag.");
+            } else if show_all {
+                app.print_all_timeboxes();
+            } else if timebox.is_none() && !remove {
+                app.print_current_timebox();
+            } else {
+                app.apply_timebox(timebox, from_start)?;
+            }
+        }
+
+        Estimate { estimate } => {
+            app.estimate_current_job(estimate)?;
+        }
+
+        Log {
+            follow,
+            clear,
+            yes,
+            current,
+            summarize,
+            date,
+            format,
+        } => {
+            if (date.is_some() || format.is_some()) && !summarize {
+                app.reject("--date and --format only apply to --summarize.");
+            } else if summarize && (follow || clear || current) {
+                app.reject("Cannot use --summarize with --follow, --clear or --current.");
+            } else if summarize {
+                let day = date.unwrap_or_else(|| app.today());
+                app.print_day_summary(day, format.unwrap_or(ReportFormat::Text))?;
+            } else if follow && clear {
+                app.reject("Cannot use --follow and --clear together.");
+            } else if current && (follow || clear) {
+                app.reject("Cannot use --current with --follow or --clear.");
+            } else if current {
+                app.print_current_log();
+            } else if clear {
+                app.clear_log(yes)?;
+            } else if follow {
+                app.follow_log()?;
+            } else {
+                app.print_log();
+            }
+        }
+
+        Watch => {
+            app.watch()?;
+        }
+
+        Meditate { seconds, intent } => {
+            for i in 0..seconds {
+                println!("{}", seconds - i);
+                thread::sleep(StdDuration::from_secs(1));
+            }
+            if let Some(intent) = intent {
+                println!("{}", intent);
+            }
+        }
+
+        Jot { tag, edit, words } => {
+            if edit {
+                if tag.is_some() || !words.is_empty() {
+                    app.reject("--edit doesn't take a --tag or any words.");
+                } else {
+                    app.edit_last_note()?;
+                }
+                return Ok(());
+            }
+            let tag = tag.map(|tag| tag.trim_start_matches('@').to_owned());
+            if let Some(tag) = &tag {
+                if !is_valid_tag(tag) {
+                    app.reject(format!(
+                        "\"{}\" isn't a valid tag. Tags can't be empty or contain spaces.",
+                        tag
+                    ));
+                    return Ok(());
+                }
+            }
+            let content = join_words(&words);
+            if content.is_empty() {
+                app.reject("Can't jot an empty note.");
+                return Ok(());
+            }
+            app.add_log_note(content, tag);
+        }
+
+        Search { tag, words } => {
+            let tag = tag.map(|tag| tag.trim_start_matches('@').to_owned());
+            let pattern = join_words(&words);
+            if tag.is_none() && pattern.is_empty() {
+                app.reject("Search for some words, or a --tag.");
+                return Ok(());
+            }
+            app.search_notes(tag.as_deref(), &pattern)?;
+        }
+
+        Work {
+            done,
+            status,
+            break_for,
+        } => {
+            if [done, status, break_for.is_some()].iter().filter(|flag| **flag).count() > 1 {
+                app.reject("Use only one of --done, --status and --break.");
+                return Ok(());
+            }
+            if status {
+                app.print_work_status();
+                return Ok(());
+            }
+            if let Some(break_for) = break_for {
+                if break_for.as_secs() == 0 {
+                    app.reject("A break needs to last some time.");
+                } else {
+                    app.take_break(break_for)?;
+                }
+                return Ok(());
+            }
+            let work_state = if done {
+                WorkState::Off
+            } else {
+                WorkState::Working
+            };
+            app.set_work_state(work_state)?;
+        }
+
+        Export {
+            format,
+            output,
+            include_board,
+            since,
+            until,
+            anonymize,
+        } => {
+            let has_board = matches!(format, ExportFormat::Json | ExportFormat::Ron);
+            if include_board && !has_board {
+                app.reject("--include-board only applies to json and ron exports.");
+            } else if matches!((since, until), (Some(since), Some(until)) if since >= until) {
+                app.reject("--since must be before --until.");
+            } else {
+                app.export(format, output, include_board, since, until, anonymize)?;
+            }
+        }
+
+        Snooze { until, words } => {
+            let pattern = join_words(&words);
+            if pattern.is_empty() {
+                app.reject("Say which suspended job to snooze.");
+                return Ok(());
+            }
+            let timer = Utc::now()
+                + Duration::from_std(until)
+                    .expect("Unable to convert std duration to chrono duration.");
+            app.snooze_job_named(&pattern, timer)?;
+        }
+
+        Pin { words } => {
+            let pattern = join_words(&words);
+            if pattern.is_empty() {
+                app.reject("Say which suspended job to pin.");
+                return Ok(());
+            }
+            app.set_pinned(&pattern, true)?;
+        }
+
+        Unpin { words } => {
+            let pattern = join_words(&words);
+            if pattern.is_empty() {
+                app.reject("Say which suspended job to unpin.");
+                return Ok(());
+            }
+            app.set_pinned(&pattern, false)?;
+        }
+
+        Stats {
+            focus,
+            streak,
+            tags,
+            now,
+            heatmap,
+            weeks,
+            per_hour,
+            per_weekday,
+            days,
+            estimate_accuracy,
+            format,
+            export,
+            output,
+            since,
+            until,
+            day,
+        } => {
+            let distribution = per_hour || per_weekday;
+            if now {
+                let other_options = focus
+                    || streak
+                    || tags
+                    || heatmap
+                    || weeks.is_some()
+                    || distribution
+                    || days.is_some()
+                    || estimate_accuracy
+                    || format.is_some()
+                    || export.is_some()
+                    || output.is_some()
+                    || since.is_some()
+                    || until.is_some()
+                    || day.is_some();
+                if other_options {
+                    app.reject("Cannot use --now with other reports or options.");
+                } else {
+                    app.print_now_stats()?;
+                }
+                return Ok(());
+            }
+            if format.is_some() && !estimate_accuracy {
+                app.reject("--format only applies to --estimate-accuracy.");
+                return Ok(());
+            }
+            if estimate_accuracy {
+                let other_options = focus
+                    || streak
+                    || tags
+                    || heatmap
+                    || weeks.is_some()
+                    || distribution
+                    || days.is_some()
+                    || export.is_some()
+                    || output.is_some()
+                    || since.is_some()
+                    || until.is_some()
+                    || day.is_some();
+                if other_options {
+                    app.reject("Cannot use --estimate-accuracy with other reports or options.");
+                } else {
+                    app.print_estimate_accuracy(format.unwrap_or(ReportFormat::Text))?;
+                }
+                return Ok(());
+            }
+            if heatmap {
+                let other_options = focus
+                    || streak
+                    || tags
+                    || distribution
+                    || days.is_some()
+                    || export.is_some()
+                    || output.is_some()
+                    || since.is_some()
+                    || until.is_some()
+                    || day.is_some();
+                if other_options {
+                    app.reject("Cannot use --heatmap with other reports or options.");
+                } else if weeks == Some(0) {
+                    app.reject("The heatmap needs at least 1 week.");
+                } else {
+                    app.print_heatmap(weeks.unwrap_or(12))?;
+                }
+                return Ok(());
+            }
+            if weeks.is_some() {
+                app.reject("--weeks only applies to --heatmap.");
+                return Ok(());
+            }
+            if distribution {
+                let other_options = focus
+                    || streak
+                    || tags
+                    || (per_hour && per_weekday)
+                    || weeks.is_some()
+                    || export.is_some()
+                    || output.is_some()
+                    || since.is_some()
+                    || until.is_some()
+                    || day.is_some();
+                if other_options {
+                    app.reject("Cannot use --per-hour or --per-weekday with other reports or options.");
+                } else if days == Some(0) {
+                    app.reject("--days needs at least 1 day.");
+                } else {
+                    app.print_distribution(per_hour, days)?;
+                }
+                return Ok(());
+            }
+            if days.is_some() {
+                app.reject("--days only applies to --per-hour and --per-weekday.");
+                return Ok(());
+            }
+            if let Some(format) = export {
+                if focus || streak || tags || day.is_some() {
+                    app.reject("Cannot use --export with --focus, --streak, --tags or --day.");
+                } else {
+                    app.export_history(format, output, since, until)?;
+                }
+                return Ok(());
+            }
+            if output.is_some() || since.is_some() || until.is_some() {
+                app.reject("--output, --since and --until only apply to --export.");
+                return Ok(());
+            }
+            if streak && (focus || tags || day.is_some()) {
+                app.reject("Cannot use --streak with --focus, --tags or --day.");
+            } else if streak {
+                app.print_streak()?;
+            } else if tags && focus {
+                app.reject("Cannot use --tags with --focus.");
+            } else if tags {
+                let day = day.unwrap_or_else(|| app.today());
+                app.print_tag_stats(day)?;
+            } else {
+                let day = day.unwrap_or_else(|| app.today());
+                app.print_focus_stats(day)?;
+            }
+        }
+
+        Replay { day, html, output } => {
+            if output.is_some() && !html {
+                app.reject("--output only applies to --html timelines.");
+                return Ok(());
+            }
+            let day = day.unwrap_or_else(|| app.today());
+            app.replay(day, html, output)?;
+        }
+
+        Timeline { since, output } => {
+            app.write_trace(since, output)?;
+        }
+
+        Mv {
+            id,
+            to,
+            reason,
+            timebox,
+        } => {
+            if to == MoveTarget::Active && timebox.is_some() {
+                app.reject("--timebox only applies when moving a job to suspended.");
+                return Ok(());
+            }
+            let options = SuspendOptions {
+                reason,
+                timer: timebox.map(|timebox| {
+                    Utc::now()
+                        + Duration::from_std(timebox)
+                            .expect("Unable to convert std duration to chrono duration.")
+                }),
+                ..default()
+            };
+            app.move_job(&id, to, options)?;
+        }
+
+        Version { .. } => {
+            // Handled before the app is loaded
+        }
+
+        Bulk { file, keep_going } => {
+            run_bulk(app, file, keep_going)?;
+        }
+
+        Context { context, clear } => match (context, clear) {
+            (Some(_), true) => app.reject("Cannot set a context while using the --clear flag."),
+            (None, true) => app.set_context(None)?,
+            (Some(context), false) => {
+                let context = context.trim_start_matches('@');
+                if !is_valid_tag(context) {
+                    app.reject(format!(
+                        "\"{}\" isn't a valid context. Contexts can't be empty or contain spaces.",
+                        context
+                    ));
+                    return Ok(());
+                }
+                app.set_context(Some(context.to_owned()))?;
+            }
+            (None, false) => match app.context() {
+                Some(context) => println!("@{}", context),
+                None => println!("No context is set."),
+            },
+        },
+
+        RenameTag { old, new } => {
+            let old = old.trim_start_matches('@');
+            let new = new.trim_start_matches('@');
+            if !is_valid_tag(new) {
+                app.reject(format!(
+                    "\"{}\" isn't a valid tag. Tags can't be empty or contain spaces.",
+                    new
+                ));
+                return Ok(());
+            }
+            app.rename_tag(old, new)?;
+        }
+
+        Prompt { max_width } => {
+            print!("{}", app.prompt_summary(max_width));
+        }
+
+        Import { file, yes } => {
+            app.import(&file, yes)?;
+        }
+
+        Compact { prune_history } => {
+            app.compact(prune_history)?;
+        }
+
+        Open { html, links } => {
+            if html && links {
+                app.reject("Cannot use --html and --links together.");
+            } else if links {
+                app.open_links();
+            } else {
+                app.open(html);
+            }
+        }
+
+        Config { key, value } => match (key, value) {
+            (None, _) => app.print_settings()?,
+            (Some(key), None) => app.print_setting(&key)?,
+            (Some(key), Some(value)) => app.change_setting(&key, &value)?,
+        },
+    };
+
+    Ok(())
+}
+
+// Splits a line into words like a shell would, honoring quotes.
+fn split_command_line(line: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        anyhow::bail!("Unterminated quote");
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+fn run_bulk(
+    app: &mut WydApplication,
+    file: Option<PathBuf>,
+    keep_going: bool,
+) -> anyhow::Result<()> {
+    let script = match file {
+        Some(path) if path.as_os_str() != "-" => fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read commands from {:?}", path))?,
+        _ => {
+            let mut script = String::new();
+            io::stdin()
+                .read_to_string(&mut script)
+                .context("Unable to read commands from stdin")?;
+            script
+        }
+    };
+
+    app.defer_saves();
+    let mut succeeded = 0;
+    let mut failed = 0;
+    for (line_index, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let rejections = app.rejection_count();
+        let outcome = split_command_line(line).and_then(|words| {
+            let args = Arguments::try_parse_from(std::iter::once("wyd".to_owned()).chain(words))?;
+            match args.subcommand {
+                Some(Command::Bulk { .. }) => anyhow::bail!("Bulk commands can't be nested"),
+                Some(Command::Version { .. }) | Some(Command::Prompt { .. }) => {
+                    anyhow::bail!("version and prompt aren't supported in bulk")
+                }
+                subcommand => run_command(app, subcommand.unwrap_or_else(default_command)),
+            }
+        });
+        match outcome {
+            Ok(()) if app.rejection_count() == rejections => succeeded += 1,
+            outcome => {
+                failed += 1;
+                match outcome {
+                    Err(error) => eprintln!("Line {} failed: {:#}", line_index + 1, error),
+                    // A rejected command has already said why
+                    Ok(()) => eprintln!("Line {} failed.", line_index + 1),
+                }
+                if !keep_going {
+                    break;
+                }
+            }
+        }
+    }
+    app.finish_deferred_saves()
+        .context("Unable to save after running bulk commands.")?;
+    println!("Bulk commands: {} succeeded, {} failed.", succeeded, failed);
+    Ok(())
+}
+
+// The default data directory, which is shared by every copy of wyd
+// on the machine even when WYD_DATA_DIR points somewhere else.
+fn base_dir() -> anyhow::Result<PathBuf> {
+    Ok(dirs::data_local_dir()
+        .context("Could not locate current user's app data folder.")?
+        .join(".wyd"))
+}
+
+// The directory holding wyd's state, which can be overridden with the
+// --app-dir flag, or else by setting the WYD_DATA_DIR environment variable.
+fn app_dir(app_dir_flag: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+    let (override_dir, override_name) = match app_dir_flag {
+        Some(dir) => (Some(dir), "--app-dir"),
+        None => (
+            std::env::var_os("WYD_DATA_DIR")
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+            "WYD_DATA_DIR",
+        ),
+    };
+    let app_dir = match &override_dir {
+        Some(dir) => dir.clone(),
+        None => base_dir()?,
+    };
+
+    fs::create_dir_all(&app_dir)
+        .with_context(|| format!("Could not create application directory {:?}", app_dir))?;
+
+    if override_dir.is_some() {
+        // Fail early with a clear message, rather than when state is saved
+        let probe_path = app_dir.join(".wyd-write-test");
+        fs::write(&probe_path, "")
+            .and_then(|()| fs::remove_file(&probe_path))
+            .with_context(|| {
+                format!(
+                    "{} points to {:?}, which is not writable",
+                    override_name, app_dir
+                )
+            })?;
+    }
+    Ok(app_dir)
+}
+
+// Must never panic, since it's the last chance to tell anyone what went wrong
+fn handle_error(error: anyhow::Error, app_dir_flag: Option<PathBuf>, debug: bool) {
+    let message = format!("{:#}", error);
+    if debug {
+        // Debug formatting lists every cause, and the backtrace if one was captured
+        eprintln!("{:?}", error);
+    }
+    let app_dir = match app_dir(app_dir_flag) {
+        Ok(app_dir) => app_dir,
+        Err(app_dir_error) => {
+            // There's nowhere to log to, so report the errors directly
+            let app_dir_message = format!("{:#}", app_dir_error);
+            if app_dir_message != message {
+                eprintln!("{}", app_dir_message);
+            }
+            if !debug {
+                eprintln!("{}", message);
+            }
+            return;
+        }
+    };
+
+    let logged = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(app_dir.join("wyd-error.log"))
+        .and_then(|mut error_log_file| writeln!(error_log_file, "{}", message));
+    if logged.is_err() && !debug {
+        // Better to show the error than lose it along with the log
+        eprintln!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // Runs `script` with bulk in a fresh data directory, and returns the labels left active
+    fn run_script(script: &str, keep_going: bool) -> Vec<String> {
+        let app_dir = tempfile::tempdir().unwrap();
+        let script_path = app_dir.path().join("script.txt");
+        fs::write(&script_path, script).unwrap();
+        let mut app = WydApplication::load(app_dir.path().to_owned()).unwrap();
+        run_bulk(&mut app, Some(script_path), keep_going).unwrap();
+        JobBoard::load(app_dir.path())
+            .active_stack
+            .into_iter()
+            .map(|job| job.label)
+            .collect()
+    }
+
+    fn command(words: &[&str]) -> Command {
+        let args = Arguments::try_parse_from(std::iter::once(&"wyd").chain(words)).unwrap();
+        args.subcommand.unwrap()
+    }
+
+    #[test]
+    fn concurrent_writers_keep_each_others_changes() {
+        let app_dir = tempfile::tempdir().unwrap();
+        let writers: Vec<_> = ["a", "b"]
+            .iter()
+            .map(|writer| {
+                let app_dir = app_dir.path().to_owned();
+                thread::spawn(move || {
+                    for i in 0..10 {
+                        let label = format!("{}{}", writer, i);
+                        let push = command(&["push", &label]);
+                        let mut app = load_app(app_dir.clone(), &push).unwrap();
+                        run_command(&mut app, push).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(JobBoard::load(app_dir.path()).active_stack.len(), 20);
+    }
+
+    #[test]
+    fn bulk_stops_at_a_rejected_line() {
+        let script = "push first\npin\npush second\n";
+        assert_eq!(run_script(script, false), ["first"]);
+        assert_eq!(run_script(script, true), ["first", "second"]);
+    }
+
+    #[test]
+    fn bulk_refuses_version_and_prompt() {
+        assert!(run_script("version\npush first\n", false).is_empty());
+        assert!(run_script("prompt\npush first\n", false).is_empty());
+    }
+
+    #[test]
+    fn retro_takes_a_duration_or_a_start_time() {
+        let now = Local.ymd(2021, 6, 4).and_hms(15, 0, 0);
+        let minutes = |input| parse_retro_at(input, now).unwrap().as_secs() / 60;
+        assert_eq!(minutes("30m"), 30);
+        assert_eq!(minutes("1h 15m"), 75);
+        assert_eq!(minutes("2pm"), 60);
+        assert_eq!(minutes("1:30pm"), 90);
+        let error = parse_retro_at("4pm", now).unwrap_err();
+        assert!(error.to_string().contains("is in the future"));
+    }
+
+    #[test]
+    fn word_taking_commands_reject_blank_words() {
+        let app_dir = tempfile::tempdir().unwrap();
+        let mut app = WydApplication::load(app_dir.path().to_owned()).unwrap();
+        let blank_commands: [&[&str]; 9] = [
+            &["suspend", "--new"],
+            &["suspend", "--new", "  "],
+            &["jot"],
+            &["jot", " "],
+            &["five-minutes"],
+            &["log-work", "30m"],
+            &["snooze", "--until", "1h"],
+            &["pin"],
+            &["unpin", "  "],
+        ];
+        for (i, words) in blank_commands.iter().enumerate() {
+            run_command(&mut app, command(words)).unwrap();
+            assert_eq!(app.rejection_count(), i as u32 + 1, "{:?} wasn't rejected", words);
+        }
+        let board = JobBoard::load(app_dir.path());
+        assert!(board.active_stack.is_empty() && board.suspended_stacks.is_empty());
+    }
+
+    #[test]
+    fn heatmap_rejects_options_it_would_ignore() {
+        let app_dir = tempfile::tempdir().unwrap();
+        let mut app = WydApplication::load(app_dir.path().to_owned()).unwrap();
+        let ignored_options: [&[&str]; 8] = [
+            &["stats", "--heatmap", "--output", "heatmap.txt"],
+            &["stats", "--heatmap", "--since", "yesterday"],
+            &["stats", "--heatmap", "--until", "yesterday"],
+            &["stats", "--heatmap", "--days", "7"],
+            &["stats", "--weeks", "4"],
+            &["stats", "--per-hour", "--weeks", "4"],
+            &["stats", "--now", "--weeks", "4"],
+            &["stats", "--estimate-accuracy", "--weeks", "4"],
+        ];
+        for (i, words) in ignored_options.iter().enumerate() {
+            run_command(&mut app, command(words)).unwrap();
+            assert_eq!(app.rejection_count(), i as u32 + 1, "{:?} wasn't rejected", words);
+        }
+    }
+
+    #[test]
+    fn distributions_reject_export_options() {
+        let app_dir = tempfile::tempdir().unwrap();
+        let mut app = WydApplication::load(app_dir.path().to_owned()).unwrap();
+        let ignored_options: [&[&str]; 3] = [
+            &["stats", "--per-hour", "--output", "hours.txt"],
+            &["stats", "--per-weekday", "--since", "yesterday"],
+            &["stats", "--per-weekday", "--until", "yesterday"],
+        ];
+        for (i, words) in ignored_options.iter().enumerate() {
+            run_command(&mut app, command(words)).unwrap();
+            assert_eq!(app.rejection_count(), i as u32 + 1, "{:?} wasn't rejected", words);
+        }
+    }
+
+    #[test]
+    fn polling_leaves_the_inactivity_clock_alone() {
+        let app_dir = tempfile::tempdir().unwrap();
+        let run = |words: &[&str]| {
+            let dir_args = ["wyd", "--app-dir", app_dir.path().to_str().unwrap()];
+            let args = Arguments::try_parse_from(dir_args.iter().chain(words)).unwrap();
+            perform_work(args).unwrap();
+        };
+        let interaction_path = app_dir.path().join(".last-interaction");
+        run(&["--poll", "ls"]);
+        run(&["stats", "--now", "--poll"]);
+        assert!(!interaction_path.exists());
+        run(&["ls"]);
+        assert!(interaction_path.exists());
+    }
+
+    #[test]
+    fn full_version_survives_a_broken_board() {
+        let app_dir = tempfile::tempdir().unwrap();
+        fs::write(app_dir.path().join("jobs.ron"), "(active_stack: [").unwrap();
+        print_version(app_dir.path(), true).unwrap();
+    }
+
+    #[test]
+    fn handle_error_never_panics() {
+        let app_dir = tempfile::tempdir().unwrap();
+        let log_path = app_dir.path().join("wyd-error.log");
+        handle_error(anyhow::anyhow!("first failure"), Some(app_dir.path().to_owned()), false);
+        assert_eq!(fs::read_to_string(&log_path).unwrap(), "first failure\n");
+
+        // Neither an unusable data directory nor an unwritable log can stop it
+        let not_a_dir = app_dir.path().join("file");
+        fs::write(&not_a_dir, "").unwrap();
+        handle_error(anyhow::anyhow!("no data dir"), Some(not_a_dir.join("wyd")), true);
+        fs::remove_file(&log_path).unwrap();
+        fs::create_dir(&log_path).unwrap();
+        handle_error(anyhow::anyhow!("no log"), Some(app_dir.path().to_owned()), false);
+    }
+
+    #[test]
+    fn log_work_records_without_touching_the_board() {
+        let app_dir = tempfile::tempdir().unwrap();
+        let mut app = WydApplication::load(app_dir.path().to_owned()).unwrap();
+        run_command(&mut app, command(&["push", "current task"])).unwrap();
+        let board_before = fs::read_to_string(app_dir.path().join("jobs.ron")).unwrap();
+
+        run_command(&mut app, command(&["log-work", "45m", "fixed", "printer", "@office"]))
+            .unwrap();
+        let board_after = fs::read_to_string(app_dir.path().join("jobs.ron")).unwrap();
+        assert_eq!(board_before, board_after);
+        let records: Vec<history::CompletionRecord> =
+            history::read_records(&app_dir.path().join("history.ron")).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].label, "fixed printer");
+        assert_eq!(records[0].tags, ["office"]);
+        let elapsed = records[0].end_date - records[0].begin_date;
+        assert_eq!(elapsed, Duration::minutes(45));
+
+        run_command(&mut app, command(&["log-work", "0s", "nothing"])).unwrap();
+        assert_eq!(app.rejection_count(), 1);
+    }
 }
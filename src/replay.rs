@@ -0,0 +1,150 @@
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, Utc};
+
+use std::time::Duration as StdDuration;
+
+use crate::{history::CompletionRecord, job::Job, job_board, settings};
+
+fn format_elapsed(begin: DateTime<Utc>, end: DateTime<Utc>) -> String {
+    let secs = end.signed_duration_since(begin).num_seconds().max(0) as u64;
+    humantime::format_duration(StdDuration::from_secs(secs)).to_string()
+}
+
+fn local_time(date: DateTime<Utc>) -> String {
+    format!("{}", settings::display_time(date).format("%r"))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The completion records that overlap `start..end`, oldest first,
+/// with subtasks after the tasks they belong to.
+pub fn records_between(
+    mut records: Vec<CompletionRecord>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<CompletionRecord> {
+    records.retain(|record| record.end_date >= start && record.begin_date < end);
+    records.sort_by_key(|record| (record.begin_date, record.depth));
+    records
+}
+
+pub fn text_timeline(records: &[CompletionRecord]) -> String {
+    let mut output = String::new();
+    for record in records {
+        output.push_str(&"    ".repeat(record.depth));
+        output.push_str(&format!(
+            "{} - {}  {}{} ({})\n",
+            local_time(record.begin_date),
+            local_time(record.end_date),
+            if record.cancelled { "(cancelled) " } else { "" },
+            record.label,
+            format_elapsed(record.begin_date, record.end_date)
+        ));
+    }
+    output
+}
+
+/// Renders the records as a Gantt chart, with one bar per task
+/// spanning the time between its first start and last finish.
+pub fn html_timeline(records: &[CompletionRecord], day: NaiveDate) -> String {
+    let mut output = job_board::html_header(&format!("Wyd replay for {}", day), None);
+    output += &format!("<h1>What you did on {}</h1>\n", day.format("%a %F"));
+
+    let span_start = records.iter().map(|record| record.begin_date).min();
+    let span_end = records.iter().map(|record| record.end_date).max();
+    let (span_start, span_end) = match (span_start, span_end) {
+        (Some(span_start), Some(span_end)) => (span_start, span_end),
+        _ => {
+            output += "<h2>No tasks were finished that day.</h2>\n</body>\n</html>\n";
+            return output;
+        }
+    };
+    let span_secs = span_end.signed_duration_since(span_start).num_seconds().max(1) as f64;
+    let percent_of_span = |date: DateTime<Utc>| {
+        date.signed_duration_since(span_start).num_seconds() as f64 * 100.0 / span_secs
+    };
+
+    output += &format!(
+        "<p>{} to {}</p>\n<div style=\"font-family: sans-serif\">\n",
+        local_time(span_start),
+        local_time(span_end)
+    );
+    for record in records {
+        let left = percent_of_span(record.begin_date);
+        // Keep very short tasks visible
+        let width = (percent_of_span(record.end_date) - left).max(0.5);
+        let label = format!(
+            "{} ({})",
+            escape_html(&record.label),
+            format_elapsed(record.begin_date, record.end_date)
+        );
+        output += &format!(
+            concat!(
+                "<div style=\"position: relative; height: 1.6em; margin: 2px 0 2px {indent}em\">",
+                "<div title=\"{label}\" style=\"position: absolute; left: {left:.2}%; width: {width:.2}%; ",
+                "height: 100%; background: {color}; border-radius: 3px\"></div>",
+                "<span style=\"position: relative; left: {left:.2}%; padding-left: 4px; ",
+                "white-space: nowrap\">{label}</span></div>\n"
+            ),
+            indent = record.depth as f64 * 1.5,
+            left = left,
+            width = width,
+            color = if record.cancelled { "#d9a0a0" } else { "#9cc4e4" },
+            label = label
+        );
+    }
+    output += "</div>\n</body>\n</html>\n";
+    output
+}
+
+fn trace_event(
+    name: &str,
+    category: &str,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+    tags: &[String],
+) -> Option<serde_json::Value> {
+    let duration = end.signed_duration_since(begin).num_microseconds()?;
+    if duration <= 0 {
+        return None;
+    }
+    Some(serde_json::json!({
+        "name": name,
+        "cat": category,
+        "ph": "X",
+        "ts": begin.timestamp_nanos() / 1000,
+        "dur": duration,
+        "pid": 1,
+        "tid": 1,
+        "args": { "tags": tags },
+    }))
+}
+
+/// Renders finished and in progress tasks in the Chrome Trace Event Format.
+/// Every task is a complete ("X") event on the same thread, so the viewer
+/// nests subtasks inside the tasks they belong to. Load the file from
+/// chrome://tracing or https://ui.perfetto.dev to browse it.
+pub fn chrome_trace(
+    records: &[CompletionRecord],
+    active_stack: &[Job],
+    now: DateTime<Utc>,
+) -> anyhow::Result<String> {
+    let finished = records.iter().filter_map(|record| {
+        let category = if record.cancelled { "cancelled" } else { "finished" };
+        trace_event(&record.label, category, record.begin_date, record.end_date, &record.tags)
+    });
+    let in_progress = active_stack
+        .iter()
+        .filter_map(|job| trace_event(&job.label, "in progress", job.begin_date, now, &job.tags));
+    let events: Vec<serde_json::Value> = finished.chain(in_progress).collect();
+    serde_json::to_string(&serde_json::json!({
+        "traceEvents": events,
+        "displayTimeUnit": "ms",
+    }))
+    .context("Failed to serialize trace.")
+}
@@ -0,0 +1,469 @@
+use anyhow::{bail, Context};
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, Offset, TimeZone, Timelike,
+    Utc, Weekday,
+};
+use chrono_tz::Tz;
+use ron::ser::{self, PrettyConfig};
+use serde::{Deserialize, Serialize};
+
+use std::{fmt, fs, path::Path, str::FromStr, sync::OnceLock, time::Duration as StdDuration};
+
+/// The days and hours work mode applies, in local time.
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Debug)]
+pub struct WorkHours {
+    pub first_day: Weekday,
+    pub last_day: Weekday,
+    pub start_hour: u32,
+    pub end_hour: u32,
+}
+
+impl WorkHours {
+    pub fn contains(&self, local_time: NaiveDateTime) -> bool {
+        let day = local_time.weekday().num_days_from_monday();
+        let first_day = self.first_day.num_days_from_monday();
+        let last_day = self.last_day.num_days_from_monday();
+        // Day ranges may wrap around the weekend, e.g. Sat-Tue
+        let on_work_day = if first_day <= last_day {
+            first_day <= day && day <= last_day
+        } else {
+            day >= first_day || day <= last_day
+        };
+        on_work_day && self.start_hour <= local_time.hour() && local_time.hour() < self.end_hour
+    }
+}
+
+impl FromStr for WorkHours {
+    type Err = anyhow::Error;
+
+    // Accepts e.g. "Mon-Fri 9-17", "Sat 10-14", or just "9-17" for every day
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usage = || format!("Expected work hours like \"Mon-Fri 9-17\", got \"{}\"", s);
+        let split_range = |range: &str| match range.split_once('-') {
+            Some((start, end)) => Ok((start.trim().to_owned(), end.trim().to_owned())),
+            None => bail!(usage()),
+        };
+        let (days, hours) = match s.trim().rsplit_once(' ') {
+            Some((days, hours)) => (Some(days), hours),
+            None => (None, s.trim()),
+        };
+        let (first_day, last_day) = match days {
+            Some(days) => {
+                let (first_day, last_day) = match days.split_once('-') {
+                    Some((first_day, last_day)) => (first_day.trim(), last_day.trim()),
+                    None => (days, days),
+                };
+                (
+                    first_day.parse().map_err(|_| anyhow::anyhow!(usage()))?,
+                    last_day.parse().map_err(|_| anyhow::anyhow!(usage()))?,
+                )
+            }
+            None => (Weekday::Mon, Weekday::Sun),
+        };
+        let (start_hour, end_hour) = split_range(hours)?;
+        let start_hour: u32 = start_hour.parse().with_context(usage)?;
+        let end_hour: u32 = end_hour.parse().with_context(usage)?;
+        if start_hour >= end_hour || end_hour > 24 {
+            bail!("Work hours must start before they end, within 0-24.");
+        }
+        Ok(WorkHours {
+            first_day,
+            last_day,
+            start_hour,
+            end_hour,
+        })
+    }
+}
+
+impl fmt::Display for WorkHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}-{:?} {}-{}",
+            self.first_day, self.last_day, self.start_hour, self.end_hour
+        )
+    }
+}
+
+// Set from the settings when they're loaded, since `Job`'s Display has no way to reach them
+static DISPLAY_TIMEZONE: OnceLock<Tz> = OnceLock::new();
+
+/// Converts `date` to `timezone`, or to the system's timezone when that's `None`.
+pub fn in_timezone(date: DateTime<Utc>, timezone: Option<Tz>) -> DateTime<FixedOffset> {
+    match timezone {
+        Some(timezone) => {
+            let date = date.with_timezone(&timezone);
+            date.with_timezone(&date.offset().fix())
+        }
+        None => {
+            let date = date.with_timezone(&Local);
+            date.with_timezone(date.offset())
+        }
+    }
+}
+
+/// When `day` starts in `timezone`, or in the system's timezone when that's `None`.
+pub fn day_start(day: NaiveDate, timezone: Option<Tz>) -> Option<DateTime<Utc>> {
+    let midnight = day.and_hms(0, 0, 0);
+    match timezone {
+        Some(timezone) => timezone
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|start| start.with_timezone(&Utc)),
+        None => Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|start| start.with_timezone(&Utc)),
+    }
+}
+
+/// Converts `date` to the configured timezone, or the system's when none is set.
+/// Prefer `in_timezone` with the loaded settings' zone where they're at hand.
+pub fn display_time(date: DateTime<Utc>) -> DateTime<FixedOffset> {
+    in_timezone(date, DISPLAY_TIMEZONE.get().copied())
+}
+
+/// The values a `log_format` template can refer to, as `{time}`, `{indent}`, etc.
+pub struct LogFields<'a> {
+    pub time: DateTime<Utc>,
+    pub timezone: Option<Tz>,
+    pub indent: &'a str,
+    pub verb: &'a str,
+    pub label: &'a str,
+    pub elapsed: StdDuration,
+}
+
+const LOG_PLACEHOLDERS: [&str; 5] = ["time", "indent", "verb", "label", "elapsed"];
+
+enum TemplatePart<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+// Splits a template into plain text and the names inside each "{...}"
+fn parse_template(template: &str) -> Vec<TemplatePart<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let after_brace = &rest[start + 1..];
+        let end = match after_brace.find('}') {
+            Some(end) => end,
+            None => break,
+        };
+        parts.push(TemplatePart::Text(&rest[..start]));
+        parts.push(TemplatePart::Placeholder(&after_brace[..end]));
+        rest = &after_brace[end + 1..];
+    }
+    parts.push(TemplatePart::Text(rest));
+    parts
+}
+
+/// Checks that a `log_format` template only uses known placeholders.
+pub fn validate_log_format(template: &str) -> anyhow::Result<()> {
+    let unknown: Vec<String> = parse_template(template)
+        .into_iter()
+        .filter_map(|part| match part {
+            TemplatePart::Placeholder(name) if !LOG_PLACEHOLDERS.contains(&name) => {
+                Some(format!("{{{}}}", name))
+            }
+            _ => None,
+        })
+        .collect();
+    if !unknown.is_empty() {
+        bail!(
+            "Unknown placeholder {} in log format. Expected some of {}.",
+            unknown.join(", "),
+            LOG_PLACEHOLDERS
+                .iter()
+                .map(|name| format!("{{{}}}", name))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+    }
+    Ok(())
+}
+
+/// Fills in a `log_format` template.
+pub fn render_log_format(template: &str, fields: &LogFields) -> String {
+    let mut output = String::new();
+    for part in parse_template(template) {
+        match part {
+            TemplatePart::Text(text) => output.push_str(text),
+            TemplatePart::Placeholder("time") => {
+                let time = in_timezone(fields.time, fields.timezone);
+                output.push_str(&time.format("%r").to_string())
+            }
+            TemplatePart::Placeholder("indent") => output.push_str(fields.indent),
+            TemplatePart::Placeholder("verb") => output.push_str(fields.verb),
+            TemplatePart::Placeholder("label") => output.push_str(fields.label),
+            TemplatePart::Placeholder("elapsed") => {
+                let elapsed = StdDuration::from_secs(fields.elapsed.as_secs());
+                output.push_str(&humantime::format_duration(elapsed).to_string())
+            }
+            // Left as is, since settings files can be edited by hand
+            TemplatePart::Placeholder(name) => {
+                output.push('{');
+                output.push_str(name);
+                output.push('}');
+            }
+        }
+    }
+    output
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    /// How many distinct backups of jobs.ron to keep around.
+    pub backup_count: usize,
+    /// Whether reminders read out the task's name instead of ringing a bell.
+    pub voice_reminders: bool,
+    /// How loud the alarm sound plays, from 0.0 (silent) to 1.0 (full volume).
+    pub volume: f32,
+    /// Whether every save also rewrites the HTML dashboard.
+    pub auto_html: bool,
+    /// When set, work mode only nags during these hours.
+    pub work_hours: Option<WorkHours>,
+    /// Suspends the active stack after this long without using wyd.
+    pub auto_suspend_after: Option<StdDuration>,
+    /// An IANA timezone like "Europe/Paris" to show times in, instead of the system's.
+    pub timezone: Option<Tz>,
+    /// A template for the lines logged when jobs start and finish, like
+    /// "{time} {indent}{verb} {label} {elapsed}". Unset keeps the built in format.
+    pub log_format: Option<String>,
+    /// Rounds the time shown for finished jobs, and their totals in stats,
+    /// up to a multiple of this, e.g. 15m for billing.
+    pub time_rounding: Option<StdDuration>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            backup_count: 20,
+            voice_reminders: false,
+            volume: 1.0,
+            auto_html: false,
+            work_hours: None,
+            auto_suspend_after: None,
+            timezone: None,
+            log_format: None,
+            time_rounding: None,
+        }
+    }
+}
+
+fn validate_volume(volume: f32) -> anyhow::Result<()> {
+    if !(0.0..=1.0).contains(&volume) {
+        bail!("Volume must be between 0.0 and 1.0, got {}", volume);
+    }
+    Ok(())
+}
+
+impl Settings {
+    pub fn load(app_dir: &Path) -> anyhow::Result<Self> {
+        let settings_path = app_dir.join("settings.ron");
+        if !settings_path.exists() {
+            return Ok(Settings::default());
+        }
+        let contents = fs::read_to_string(&settings_path)
+            .with_context(|| format!("Failed to read settings file {:?}", settings_path))?;
+        if contents.trim().is_empty() {
+            return Ok(Settings::default());
+        }
+        let settings: Settings = ron::from_str(&contents)
+            .with_context(|| format!("Settings file at {:?} is malformed.", settings_path))?;
+        validate_volume(settings.volume)
+            .with_context(|| format!("Settings file at {:?} is invalid.", settings_path))?;
+        if let Some(timezone) = settings.timezone {
+            // Only the first load of a run takes effect
+            let _ = DISPLAY_TIMEZONE.set(timezone);
+        }
+        Ok(settings)
+    }
+
+    pub fn save(&self, app_dir: &Path) -> anyhow::Result<()> {
+        let text = ser::to_string_pretty(self, PrettyConfig::new())
+            .context("Failed to serialize settings.")?;
+        fs::write(app_dir.join("settings.ron"), text).context("Failed to write settings file.")?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> anyhow::Result<String> {
+        let value = match key {
+            "backup_count" => self.backup_count.to_string(),
+            "voice_reminders" => self.voice_reminders.to_string(),
+            "volume" => self.volume.to_string(),
+            "auto_html" => self.auto_html.to_string(),
+            "work_hours" => match self.work_hours {
+                Some(work_hours) => work_hours.to_string(),
+                None => "none".to_owned(),
+            },
+            "auto_suspend_after" => match self.auto_suspend_after {
+                Some(duration) => humantime::format_duration(duration).to_string(),
+                None => "none".to_owned(),
+            },
+            "timezone" => match self.timezone {
+                Some(timezone) => timezone.name().to_owned(),
+                None => "none".to_owned(),
+            },
+            "log_format" => match &self.log_format {
+                Some(log_format) => log_format.clone(),
+                None => "none".to_owned(),
+            },
+            "time_rounding" => match self.time_rounding {
+                Some(duration) => humantime::format_duration(duration).to_string(),
+                None => "none".to_owned(),
+            },
+            _ => bail!("Unknown setting \"{}\"", key),
+        };
+        Ok(value)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        match key {
+            "backup_count" => {
+                self.backup_count = value
+                    .parse()
+                    .with_context(|| format!("Invalid backup count \"{}\"", value))?;
+            }
+            "voice_reminders" => {
+                self.voice_reminders = value
+                    .parse()
+                    .with_context(|| format!("Expected true or false, got \"{}\"", value))?;
+            }
+            "volume" => {
+                let volume: f32 = value
+                    .parse()
+                    .with_context(|| format!("Expected a volume like 0.5, got \"{}\"", value))?;
+                validate_volume(volume)?;
+                self.volume = volume;
+            }
+            "auto_html" => {
+                self.auto_html = value
+                    .parse()
+                    .with_context(|| format!("Expected true or false, got \"{}\"", value))?;
+            }
+            "work_hours" => {
+                self.work_hours = match value {
+                    "none" => None,
+                    _ => Some(value.parse()?),
+                };
+            }
+            "auto_suspend_after" => {
+                self.auto_suspend_after = match value {
+                    "none" => None,
+                    _ => Some(humantime::parse_duration(value).with_context(|| {
+                        format!("Expected a duration like \"4h\" or none, got \"{}\"", value)
+                    })?),
+                };
+            }
+            "timezone" => {
+                self.timezone = match value {
+                    "none" => None,
+                    _ => Some(value.parse().map_err(|_| {
+                        anyhow::anyhow!(
+                            "Expected a timezone like \"America/New_York\" or none, got \"{}\"",
+                            value
+                        )
+                    })?),
+                };
+            }
+            "log_format" => {
+                self.log_format = match value {
+                    "none" => None,
+                    _ => {
+                        validate_log_format(value)?;
+                        Some(value.to_owned())
+                    }
+                };
+            }
+            "time_rounding" => {
+                self.time_rounding = match value {
+                    "none" => None,
+                    _ => {
+                        let increment = humantime::parse_duration(value).with_context(|| {
+                            format!("Expected a duration like \"15m\" or none, got \"{}\"", value)
+                        })?;
+                        if increment.as_secs() == 0 {
+                            bail!("Time rounding must be at least a second.");
+                        }
+                        Some(increment)
+                    }
+                };
+            }
+            _ => bail!("Unknown setting \"{}\"", key),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_instant_formats_in_each_zone() {
+        let time = Utc.ymd(2021, 6, 1).and_hms(23, 30, 0);
+        let render = |timezone| {
+            let fields = LogFields {
+                time,
+                timezone: Some(timezone),
+                indent: "",
+                verb: "Started",
+                label: "call",
+                elapsed: StdDuration::ZERO,
+            };
+            render_log_format("{time} {verb} {label}", &fields)
+        };
+        assert_eq!(render(chrono_tz::UTC), "11:30:00 PM Started call");
+        assert_eq!(render(chrono_tz::Asia::Tokyo), "08:30:00 AM Started call");
+
+        // Tokyo is already into the next day
+        let tokyo_day = in_timezone(time, Some(chrono_tz::Asia::Tokyo)).naive_local().date();
+        assert_eq!(tokyo_day, NaiveDate::from_ymd(2021, 6, 2));
+        assert_eq!(
+            day_start(tokyo_day, Some(chrono_tz::Asia::Tokyo)),
+            Some(Utc.ymd(2021, 6, 1).and_hms(15, 0, 0))
+        );
+    }
+
+    #[test]
+    fn out_of_range_volume_is_rejected() {
+        let mut settings = Settings::default();
+        assert!(settings.set("volume", "5").is_err());
+        assert!(settings.set("volume", "-0.1").is_err());
+        settings.set("volume", "1").unwrap();
+        assert_eq!(settings.volume, 1.0);
+
+        let app_dir = tempfile::tempdir().unwrap();
+        let text = ser::to_string(&Settings {
+            volume: 5.0,
+            ..Settings::default()
+        })
+        .unwrap();
+        fs::write(app_dir.path().join("settings.ron"), text).unwrap();
+        assert!(Settings::load(app_dir.path()).is_err());
+    }
+
+    #[test]
+    fn custom_log_format_renders_every_placeholder() {
+        let fields = LogFields {
+            time: Utc.ymd(2021, 6, 1).and_hms(14, 5, 0),
+            timezone: Some(chrono_tz::UTC),
+            indent: "  ",
+            verb: "Finished",
+            label: "write tests",
+            elapsed: StdDuration::from_millis(95_400),
+        };
+        let template = "{time}|{indent}|{verb}|{label}|{elapsed}|{unknown}";
+        assert_eq!(
+            render_log_format(template, &fields),
+            "02:05:00 PM|  |Finished|write tests|1m 35s|{unknown}"
+        );
+
+        let mut settings = Settings::default();
+        settings.set("log_format", "{time},{label},{elapsed}").unwrap();
+        assert!(settings.set("log_format", "{label} {duration}").is_err());
+        assert_eq!(settings.log_format.as_deref(), Some("{time},{label},{elapsed}"));
+    }
+}
@@ -0,0 +1,394 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc};
+use chrono_tz::Tz;
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    str::FromStr,
+    time::Duration as StdDuration,
+};
+
+use crate::{
+    history::{CompletionRecord, FocusRecord, FocusState},
+    settings,
+};
+
+/// Total time spent focused and slacking between `start` and `end`,
+/// given focus records sorted oldest first.
+pub fn focus_totals(
+    records: &[FocusRecord],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> (Duration, Duration) {
+    let mut focused = Duration::zero();
+    let mut slacking = Duration::zero();
+    for (i, record) in records.iter().enumerate() {
+        // Each record's state lasts until the next record
+        let state_end = match records.get(i + 1) {
+            Some(next_record) => next_record.date.min(end),
+            None => end,
+        };
+        let state_start = record.date.max(start);
+        if state_end <= state_start {
+            continue;
+        }
+        let time_in_state = state_end.signed_duration_since(state_start);
+        match record.state {
+            FocusState::Focused => focused = focused + time_in_state,
+            FocusState::Slacking => slacking = slacking + time_in_state,
+            FocusState::Off => {}
+        }
+    }
+    (focused, slacking)
+}
+
+/// Rounds `duration` up to a whole number of `increment`s, for `time_rounding`.
+pub fn round_up(duration: StdDuration, increment: Option<StdDuration>) -> StdDuration {
+    let increment = match increment {
+        Some(increment) if increment.as_secs() > 0 => increment.as_secs(),
+        _ => return duration,
+    };
+    StdDuration::from_secs(duration.as_secs().div_ceil(increment) * increment)
+}
+
+/// Time spent on finished jobs between `start` and `end`, per tag. A job's
+/// time goes to the tag given with `done --tag-time`, or else to each of its tags,
+/// rounded up to the `rounding` increment.
+pub fn time_by_tag(
+    records: &[CompletionRecord],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    rounding: Option<StdDuration>,
+) -> BTreeMap<String, Duration> {
+    let mut totals = BTreeMap::new();
+    for record in records {
+        let job_start = record.begin_date.max(start);
+        let job_end = record.end_date.min(end);
+        if job_end <= job_start {
+            continue;
+        }
+        let secs = job_end.signed_duration_since(job_start).num_seconds().max(0) as u64;
+        let rounded = round_up(StdDuration::from_secs(secs), rounding);
+        let time_spent = Duration::seconds(rounded.as_secs() as i64);
+        let tags = match &record.time_tag {
+            Some(time_tag) => std::slice::from_ref(time_tag),
+            None => &record.tags[..],
+        };
+        for tag in tags {
+            let total = totals.entry(tag.clone()).or_insert_with(Duration::zero);
+            *total = *total + time_spent;
+        }
+    }
+    totals
+}
+
+/// The total time covered by any of the `spans`, counting overlaps once,
+/// e.g. so a task and its subtasks aren't counted twice.
+pub fn covered_time(mut spans: Vec<(DateTime<Utc>, DateTime<Utc>)>) -> Duration {
+    spans.sort();
+    let mut total = Duration::zero();
+    let mut covered_until: Option<DateTime<Utc>> = None;
+    for (start, end) in spans {
+        let start = match covered_until {
+            Some(covered_until) => start.max(covered_until),
+            None => start,
+        };
+        if end > start {
+            total = total + end.signed_duration_since(start);
+            covered_until = Some(end);
+        }
+    }
+    total
+}
+
+/// The start of `day` and of the day after, in `timezone` or else the system's.
+pub fn day_bounds(day: NaiveDate, timezone: Option<Tz>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    Some((
+        settings::day_start(day, timezone)?,
+        settings::day_start(day.succ(), timezone)?,
+    ))
+}
+
+/// The current and longest runs of consecutive days in `days`. A streak
+/// that reached yesterday is still current, since today isn't over yet.
+pub fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (u32, u32) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous_day: Option<NaiveDate> = None;
+    for &day in days {
+        run = match previous_day {
+            Some(previous_day) if previous_day.succ() == day => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous_day = Some(day);
+    }
+    let current = match previous_day {
+        Some(last_day) if last_day == today || last_day.succ() == today => run,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+// From no completions up to the busiest day
+const HEATMAP_SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// Draws a calendar of the last `weeks` weeks, one column per week and one
+/// row per weekday, shading each day by how many tasks were finished on it.
+pub fn heatmap(counts: &HashMap<NaiveDate, u32>, today: NaiveDate, weeks: u32) -> String {
+    let this_monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let first_monday = this_monday - Duration::weeks(weeks.saturating_sub(1) as i64);
+    let busiest = counts
+        .iter()
+        .filter(|(day, _)| **day >= first_monday && **day <= today)
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0);
+
+    let mut output = String::new();
+    for (row, weekday) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"].iter().enumerate() {
+        output.push_str(weekday);
+        output.push(' ');
+        for week in 0..weeks {
+            let day = first_monday + Duration::weeks(week as i64) + Duration::days(row as i64);
+            let shade = if day > today {
+                ' '
+            } else {
+                let count = counts.get(&day).copied().unwrap_or(0);
+                // Rounds up, so any completions at all get at least the lightest shade
+                let level = (count * 4 + busiest.saturating_sub(1)) / busiest.max(1);
+                HEATMAP_SHADES[level.min(4) as usize]
+            };
+            output.push(shade);
+        }
+        let trimmed_length = output.trim_end().len();
+        output.truncate(trimmed_length);
+        output.push('\n');
+    }
+    output.push_str(&format!(
+        "    Less {} More\n",
+        HEATMAP_SHADES.iter().collect::<String>()
+    ));
+    output
+}
+
+/// How many of the finished `records` ended in each hour of the day, in `timezone`
+/// or else the system's.
+pub fn per_hour<'a>(
+    records: impl IntoIterator<Item = &'a CompletionRecord>,
+    timezone: Option<Tz>,
+) -> [u32; 24] {
+    let mut counts = [0; 24];
+    for record in records.into_iter().filter(|record| !record.cancelled) {
+        counts[settings::in_timezone(record.end_date, timezone).hour() as usize] += 1;
+    }
+    counts
+}
+
+/// How many of the finished `records` ended on each day of the week, Monday first.
+pub fn per_weekday<'a>(
+    records: impl IntoIterator<Item = &'a CompletionRecord>,
+    timezone: Option<Tz>,
+) -> [u32; 7] {
+    let mut counts = [0; 7];
+    for record in records.into_iter().filter(|record| !record.cancelled) {
+        let weekday = settings::in_timezone(record.end_date, timezone).weekday();
+        counts[weekday.num_days_from_monday() as usize] += 1;
+    }
+    counts
+}
+
+// The longest bar in a --per-hour or --per-weekday chart
+const BAR_CHART_WIDTH: u32 = 40;
+
+/// Draws a labelled bar for each count, with the biggest count filling the chart.
+pub fn bar_chart(bars: &[(String, u32)]) -> String {
+    let biggest = bars.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let label_width = bars.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    let mut output = String::new();
+    for (label, count) in bars {
+        // Rounds up, so any count at all gets at least some bar
+        let length = (count * BAR_CHART_WIDTH + biggest.saturating_sub(1)) / biggest.max(1);
+        let mut bar = "█".repeat(length as usize);
+        if !bar.is_empty() {
+            bar.push(' ');
+        }
+        output.push_str(&format!(
+            "{:width$} {}{}\n",
+            label,
+            bar,
+            count,
+            width = label_width
+        ));
+    }
+    output
+}
+
+/// How closely estimates matched the time jobs actually took.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EstimateAccuracy {
+    pub count: usize,
+    /// The mean absolute percentage error, relative to the time actually taken.
+    pub mean_error: f64,
+    /// Positive when jobs took longer than estimated, negative when they were quicker.
+    pub mean_bias: f64,
+}
+
+/// Compares each estimated job's estimate with how long it took. Cancelled
+/// jobs, and jobs that took no time at all, are left out.
+pub fn estimate_accuracy<'a>(
+    records: impl IntoIterator<Item = &'a CompletionRecord>,
+) -> Option<EstimateAccuracy> {
+    let mut count = 0;
+    let mut total_error = 0.0;
+    let mut total_bias = 0.0;
+    for record in records {
+        let estimate = match record.estimate {
+            Some(estimate) if !record.cancelled => estimate.as_secs_f64(),
+            _ => continue,
+        };
+        let actual = record.end_date.signed_duration_since(record.begin_date).num_seconds() as f64;
+        if actual <= 0.0 {
+            continue;
+        }
+        let error = (actual - estimate) / actual;
+        count += 1;
+        total_error += error.abs();
+        total_bias += error;
+    }
+    if count == 0 {
+        return None;
+    }
+    Some(EstimateAccuracy {
+        count,
+        mean_error: total_error / count as f64,
+        mean_bias: total_bias / count as f64,
+    })
+}
+
+/// How a report is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Markdown,
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(ReportFormat::Text),
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            _ => anyhow::bail!("Unknown format \"{}\". Expected one of: text, markdown", s),
+        }
+    }
+}
+
+/// Lays out `rows` under `headers`, padding each column to its widest cell.
+pub fn table(headers: &[&str], rows: &[Vec<String>], format: ReportFormat) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let format_row = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        match format {
+            ReportFormat::Text => padded.join("  ").trim_end().to_owned() + "\n",
+            ReportFormat::Markdown => format!("| {} |\n", padded.join(" | ")),
+        }
+    };
+
+    let mut output = format_row(headers.to_vec());
+    match format {
+        ReportFormat::Text => {}
+        ReportFormat::Markdown => {
+            let dashes: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+            output += &format_row(dashes.iter().map(String::as_str).collect());
+        }
+    }
+    for row in rows {
+        output += &format_row(row.iter().map(String::as_str).collect());
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A job that took `minutes`, estimated at `estimate_minutes`
+    fn record(minutes: i64, estimate_minutes: Option<u64>) -> CompletionRecord {
+        CompletionRecord {
+            estimate: estimate_minutes.map(|minutes| StdDuration::from_secs(minutes * 60)),
+            ..CompletionRecord::finished_after("estimated", minutes)
+        }
+    }
+
+    #[test]
+    fn rounding_goes_up_past_each_increment() {
+        let quarter_hour = Some(StdDuration::from_secs(15 * 60));
+        let rounded = |secs| round_up(StdDuration::from_secs(secs), quarter_hour).as_secs() / 60;
+        assert_eq!(rounded(0), 0);
+        assert_eq!(rounded(1), 15);
+        assert_eq!(rounded(15 * 60), 15);
+        assert_eq!(rounded(15 * 60 + 1), 30);
+        assert_eq!(rounded(30 * 60), 30);
+
+        let raw = StdDuration::from_secs(15 * 60 + 1);
+        assert_eq!(round_up(raw, None), raw);
+        assert_eq!(round_up(raw, Some(StdDuration::ZERO)), raw);
+    }
+
+    #[test]
+    fn estimate_accuracy_averages_error_and_bias() {
+        let mut cancelled = record(10, Some(60));
+        cancelled.cancelled = true;
+        let records = [
+            // Took twice as long as estimated: 50% error, under-estimated
+            record(60, Some(30)),
+            // Took half as long: 100% error, over-estimated
+            record(30, Some(60)),
+            // Spot on
+            record(45, Some(45)),
+            record(20, None),
+            record(0, Some(5)),
+            cancelled,
+        ];
+        let accuracy = estimate_accuracy(&records).unwrap();
+        assert_eq!(accuracy.count, 3);
+        assert!((accuracy.mean_error - 0.5).abs() < 1e-9);
+        assert!((accuracy.mean_bias - (0.5 - 1.0) / 3.0).abs() < 1e-9);
+
+        assert!(estimate_accuracy(&[record(20, None)]).is_none());
+    }
+
+    #[test]
+    fn completions_bucket_by_local_hour_and_weekday() {
+        let mut cancelled = record(30, None);
+        cancelled.cancelled = true;
+        // Ending 09:30, 09:45 and 10:00 on Friday, and 00:30 on Saturday, in UTC
+        let records = [record(30, None), record(45, None), record(60, None), record(930, None)];
+        let all = records.iter().chain(std::iter::once(&cancelled));
+
+        let hours = per_hour(all.clone(), Some(chrono_tz::UTC));
+        assert_eq!((hours[0], hours[9], hours[10]), (1, 2, 1));
+        assert_eq!(hours.iter().sum::<u32>(), 4);
+        let tokyo_hours = per_hour(all.clone(), Some(chrono_tz::Asia::Tokyo));
+        assert_eq!((tokyo_hours[9], tokyo_hours[18], tokyo_hours[19]), (1, 2, 1));
+
+        let weekdays = per_weekday(all, Some(chrono_tz::UTC));
+        assert_eq!(weekdays, [0, 0, 0, 0, 3, 1, 0]);
+        assert_eq!(per_hour(&[], None), [0; 24]);
+
+        let chart = bar_chart(&[("9".to_owned(), 2), ("10".to_owned(), 0)]);
+        let lines: Vec<&str> = chart.lines().collect();
+        assert!(lines[0].starts_with("9  █") && lines[0].ends_with("█ 2"));
+        assert_eq!(lines[1], "10 0");
+    }
+}
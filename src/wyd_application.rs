@@ -1,13 +1,18 @@
 use anyhow::{Context, Result, bail};
-use chrono::{DateTime, Duration, Local, Utc};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveTime, Timelike, Utc};
 use uuid::Uuid;
 
 use std::{
+    cell::Cell,
+    collections::{BTreeSet, HashMap},
+    ffi::OsStr,
     fmt::Display,
     fs::{self, File, OpenOptions},
-    io::{Read, Write},
-    path::PathBuf,
-    process::Command,
+    io::{self, IsTerminal, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    rc::Rc,
+    sync::mpsc,
     time::Duration as StdDuration,
 };
 
@@ -16,35 +21,188 @@ extern crate clap;
 // use notify_rust::Notification;
 use ron::ser::{self, PrettyConfig};
 
+use fs2::FileExt;
+use notify::{RecursiveMode, Watcher};
 use url::Url;
 
 use std::io::BufReader;
-use rodio::{Decoder, OutputStream, source::Source};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, StreamError, source::Source};
 
-use crate::{job::Job, job_board::WorkState};
 use crate::{
-    job_board::{JobBoard, SuspendedStack},
-    substring_matcher,
+    export::{self, Anonymizer, ExportData, ExportFormat, HistoryFormat},
+    goals::{self, DayGoals, GoalKind, GoalProgress},
+    history::{self, CompletionRecord, FocusRecord, FocusState, NoteRecord},
+    import,
+    job::{new_id, Job, Priority, ReminderPolicy},
+    job_board::WorkState,
+    replay,
+    settings::{self, Settings},
+    stats::{self, ReportFormat},
+};
+use crate::{
+    job_board::{JobBoard, MoveTarget, StackSort, SuspendOptions},
+    fuzzy_matcher, fuzzy_score, substring_matcher,
 };
 
 pub struct TimerState {
     needs_save: bool,
-    send_alarm: bool
+    send_alarm: bool,
+    // Labels of the tasks the alarm is about
+    reminders: Vec<String>,
+}
+
+/// Describes a job to be pushed onto the active stack.
+#[derive(Clone, Default)]
+pub struct PushOptions {
+    pub timebox: Option<StdDuration>,
+    pub retro: Option<StdDuration>,
+    pub depends_on: Option<String>,
+    pub reminder_policy: Option<ReminderPolicy>,
+    pub tags: Vec<String>,
+    pub links: Vec<Url>,
+    pub quiet_until: Option<DateTime<Utc>>,
+    pub priority: Priority,
+    /// Queues the job without starting its clock, until `wyd start`.
+    pub start_paused: bool,
+    /// Jotted down as notes on the job once it's started.
+    pub notes: Vec<String>,
+}
+
+// Day summaries only list this many notes, so they stay short
+const MAX_SUMMARY_NOTES: usize = 5;
+
+// Only recent mistakes can be undone with `done --undo`
+const UNDO_LIMIT_HOURS: i64 = 12;
+
+// How long `wyd watch` lets a burst of writes settle, and how many times it
+// rereads a jobs.ron that's still being written before giving up until the next change.
+const WATCH_DEBOUNCE_MILLIS: u64 = 200;
+const WATCH_RETRIES: u32 = 5;
+
+/// Describes which jobs `done` finishes, and how it reports them.
+#[derive(Default)]
+pub struct DoneOptions {
+    pub cancelled: bool,
+    pub count: usize,
+    pub show_time: bool,
+    pub json: bool,
+    pub time_tag: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// Describes how a suspended job should be found and resumed.
+#[derive(Default)]
+pub struct ResumeOptions {
+    pub fuzzy: bool,
+    pub first: bool,
+    pub keep_times: bool,
+    pub keep_suspended: bool,
+    /// Picks the most recently suspended stack instead of matching a pattern.
+    pub newest: bool,
+    /// Picks the least recently suspended stack instead of matching a pattern.
+    pub oldest: bool,
+    /// Requires the current top job to be able to take the stack as subtasks.
+    pub stack_into_current: bool,
+    /// A fresh timebox for the resumed top job.
+    pub timebox: Option<StdDuration>,
+    /// Chooses the stack with an interactive picker, when there's a terminal to show it on.
+    pub pick: bool,
+    /// Lengthens timeboxes by the time spent suspended, for use with `keep_times`.
+    pub timebox_remaining: bool,
+}
+
+// Lets the user pick one of `entries` with fzf, returning its index.
+// Err if fzf couldn't be run at all, Ok(None) if nothing was picked.
+fn pick_with_fzf(entries: &[String], query: &str) -> io::Result<Option<usize>> {
+    let mut child = Command::new("fzf")
+        .arg("--prompt=resume> ")
+        .arg("--delimiter=\t")
+        .arg("--with-nth=2..")
+        .arg(format!("--query={}", query))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        for (index, entry) in entries.iter().enumerate() {
+            writeln!(stdin, "{}\t{}", index, entry)?;
+        }
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let chosen = String::from_utf8_lossy(&output.stdout);
+    Ok(chosen
+        .split('\t')
+        .next()
+        .and_then(|index| index.trim().parse().ok())
+        .filter(|index| *index < entries.len()))
 }
 
-fn should_notify(last_notified: &Option<DateTime<Utc>>) -> bool {
-    // We only send one notification to avoid spam.
-    // Later, we can think about sequence of contingency notifications,
-    // But for now this is the simplest way.
+// A minimal stand in for fzf: narrows the list by fuzzy matching
+// whatever is typed, until a number is chosen.
+fn pick_with_prompt(entries: &[String], query: &str) -> Option<usize> {
+    let mut query = query.to_owned();
+    loop {
+        let mut matches: Vec<(i64, usize)> = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| Some((fuzzy_score(&query, entry)?, index)))
+            .collect();
+        matches.sort_by_key(|(score, index)| (-score, *index));
+        if matches.is_empty() {
+            println!("Nothing matches \"{}\".", query);
+        }
+        for (number, (_score, index)) in matches.iter().enumerate() {
+            println!("{:>4}) {}", number + 1, entries[*index]);
+        }
+        print!("Choose a task by number, type to filter, or press enter to cancel: ");
+        io::stdout().flush().ok()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer).ok()?;
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return None;
+        }
+        match answer.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= matches.len() => {
+                return Some(matches[number - 1].1)
+            }
+            _ => query = answer.to_owned(),
+        }
+    }
+}
+
+// Reminders back off exponentially so that a task left expired
+// doesn't ping every few seconds forever: 30s, 1m, 2m, ... up to 10m.
+const BASE_NOTIFICATION_INTERVAL_SECS: i64 = 30;
+const MAX_NOTIFICATION_INTERVAL_SECS: i64 = 10 * 60;
+
+fn notification_interval(notification_count: u32) -> Duration {
+    let doublings = notification_count.saturating_sub(1).min(16);
+    let interval_secs = BASE_NOTIFICATION_INTERVAL_SECS * 2_i64.pow(doublings);
+    Duration::seconds(interval_secs.min(MAX_NOTIFICATION_INTERVAL_SECS))
+}
+
+fn should_notify(
+    last_notified: &Option<DateTime<Utc>>,
+    notification_count: u32,
+    policy: Option<ReminderPolicy>,
+    now: DateTime<Utc>,
+) -> bool {
     let last_notified = match last_notified {
         Some(date) => date,
         None => return true,
     };
-    if Utc::now().signed_duration_since(*last_notified) > Duration::seconds(30) {
-        true
-    } else {
-        false
-    }
+    let interval = match policy.unwrap_or(ReminderPolicy::Backoff) {
+        ReminderPolicy::Once => return false,
+        ReminderPolicy::Every(interval) => match Duration::from_std(interval) {
+            Ok(interval) => interval,
+            Err(_out_of_range) => return false,
+        },
+        ReminderPolicy::Backoff => notification_interval(notification_count),
+    };
+    now.signed_duration_since(*last_notified) > interval
 }
 
 // fn play_alarm() -> Result<()> {
@@ -56,64 +214,501 @@ fn should_notify(last_notified: &Option<DateTime<Utc>>) -> bool {
 //     Ok(())
 // }
 
-fn play_alarm() -> Result<()> {
-    let (_stream, stream_handle) = OutputStream::try_default().unwrap();
+fn play_alarm(volume: f32) -> Result<()> {
+    play_alarm_on(OutputStream::try_default, volume)
+}
+
+// Takes the output to open so tests can stand in for a machine without a sound device
+fn play_alarm_on(
+    open_output: impl FnOnce() -> Result<(OutputStream, OutputStreamHandle), StreamError>,
+    volume: f32,
+) -> Result<()> {
+    let (_stream, stream_handle) =
+        open_output().context("No audio output device is available")?;
     let audio_bytes : &[u8] = include_bytes!("audio/bell.wav");
     //let file = BufReader::new((&include_bytes!("audio/bell.wav").read_u8()));//BufReader::new(File::open(r"C:\Windows\Media\Alarm01.wav").unwrap());
     let cursor = std::io::Cursor::new(audio_bytes);
     let reader = BufReader::new(cursor);
-    let source = Decoder::new(reader).unwrap();
-    stream_handle.play_raw(source.convert_samples())?;
+    let source = Decoder::new(reader).context("Unable to decode the alarm sound")?;
+    stream_handle.play_raw(source.convert_samples().amplify(volume))?;
     std::thread::sleep(std::time::Duration::from_secs(5));
     Ok(())
 }
 
 
+/// Held from loading the board until a command is done changing it, so two
+/// wyd processes can't load the same board and then overwrite each other's changes.
+/// It's let go while a command waits on the user, see `WydApplication::unlocked`.
+pub struct StateLock {
+    file: File,
+}
+
+// Long enough to wait out another command's save, short enough not to hang on a stuck one
+const STATE_LOCK_WAIT_MILLIS: u64 = 5000;
+const STATE_LOCK_POLL_MILLIS: u64 = 50;
+
+impl StateLock {
+    fn open(app_dir: &Path) -> anyhow::Result<File> {
+        fs::create_dir_all(app_dir)
+            .with_context(|| format!("Unable to create data directory {:?}", app_dir))?;
+        let path = app_dir.join(".wyd.lock");
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("Unable to open lock file {:?}", path))
+    }
+
+    /// Waits a little while for any other command to finish with the board.
+    pub fn acquire(app_dir: &Path) -> anyhow::Result<StateLock> {
+        let file = Self::open(app_dir)?;
+        let mut waited = 0;
+        // The OS releases the lock if its holder exits, so it can't go stale
+        while file.try_lock_exclusive().is_err() {
+            if waited >= STATE_LOCK_WAIT_MILLIS {
+                bail!("Another wyd command is still using {:?}. Try again once it's done.", app_dir);
+            }
+            std::thread::sleep(StdDuration::from_millis(STATE_LOCK_POLL_MILLIS));
+            waited += STATE_LOCK_POLL_MILLIS;
+        }
+        Ok(StateLock { file })
+    }
+
+    /// Takes the lock only if nobody else holds it.
+    fn try_acquire(app_dir: &Path) -> Option<StateLock> {
+        let file = Self::open(app_dir).ok()?;
+        file.try_lock_exclusive().ok()?;
+        Some(StateLock { file })
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        FileExt::unlock(&self.file).ok();
+    }
+}
+
+// A lock held while a notifier plays a reminder out loud. It lives in the
+// shared base directory, so notifiers for different data directories take
+// turns instead of playing over each other.
+struct AlarmGuard {
+    path: PathBuf,
+}
+
+// Alarms last about five seconds, so a lock this old was left behind by a crash
+const ALARM_LOCK_STALE_SECS: u64 = 30;
+const ALARM_WAIT_SECS: u64 = 30;
+
+impl AlarmGuard {
+    /// Waits for any other notifier's alarm to finish. Returns None if the
+    /// lock can't be used, in which case the alarm plays regardless.
+    fn acquire(owner: &Path) -> Option<AlarmGuard> {
+        let base_dir = crate::base_dir().ok()?;
+        fs::create_dir_all(&base_dir).ok()?;
+        let path = base_dir.join(".alarm");
+        for _ in 0..ALARM_WAIT_SECS {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    // Records whose alarm is playing, for anyone wondering
+                    write!(file, "{}", owner.display()).ok();
+                    return Some(AlarmGuard { path });
+                }
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                    let age = fs::metadata(&path)
+                        .and_then(|metadata| metadata.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok());
+                    match age {
+                        Some(age) if age.as_secs() >= ALARM_LOCK_STALE_SECS => {
+                            fs::remove_file(&path).ok();
+                        }
+                        _ => std::thread::sleep(StdDuration::from_secs(1)),
+                    }
+                }
+                Err(_) => return None,
+            }
+        }
+        None
+    }
+}
+
+impl Drop for AlarmGuard {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+// Writes to a temporary file first, then moves it into place, so that
+// readers never see a partially written file.
+fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path)
+}
+
+// Finds the `%r` timestamp in a log line, either from a jotted note
+// ("09:15:00 AM: note") or a new job ("label | started at 09:15:00 AM").
+fn log_line_time(line: &str) -> Option<NaiveTime> {
+    let line = line.trim_start();
+    if let Some((prefix, _)) = line.split_once(": ") {
+        if let Ok(time) = NaiveTime::parse_from_str(prefix, "%r") {
+            return Some(time);
+        }
+    }
+    let (_, rest) = line.split_once(" | started at ")?;
+    let timestamp = rest.split(" | ").next()?;
+    NaiveTime::parse_from_str(timestamp, "%r").ok()
+}
+
+// Reads text aloud using the platform's built in speech synthesizer.
+// The text goes after `--`, so a label like "-v" isn't taken for an option
+fn speech_command(text: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut command = Command::new("powershell");
+        command
+            .arg("-NoProfile")
+            .arg("-Command")
+            .arg(
+                "Add-Type -AssemblyName System.Speech; \
+                (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak($env:WYD_SPEECH)",
+            )
+            .env("WYD_SPEECH", text);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("say");
+        command.arg("--").arg(text);
+        command
+    } else {
+        let mut command = Command::new("espeak");
+        command.arg("--").arg(text);
+        command
+    }
+}
+
+fn speak(text: &str) -> Result<()> {
+    let mut command = speech_command(text);
+    let status = command
+        .status()
+        .context("Unable to start text-to-speech program")?;
+    if !status.success() {
+        bail!("Text-to-speech program exited with {}", status);
+    }
+    Ok(())
+}
+
+// Lets the user write some text in $VISUAL or $EDITOR, the way `git commit` does,
+// starting from `text`. Lines starting with '#' are left out, so `instructions`
+// can explain what to write. Returns None without a terminal or a configured editor.
+fn compose_in_editor(
+    path: &Path,
+    text: &str,
+    instructions: &str,
+) -> anyhow::Result<Option<String>> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Ok(None);
+    }
+    let editor = match std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")) {
+        Ok(editor) if !editor.trim().is_empty() => editor,
+        _ => return Ok(None),
+    };
+    let comments: String = instructions.lines().map(|line| format!("\n# {}", line)).collect();
+    fs::write(path, format!("{}{}\n", text, comments)).with_context(|| format!("Unable to write {:?}", path))?;
+    // Editors like "code --wait" come with arguments of their own
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or_default();
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Unable to run the editor \"{}\"", editor))?;
+    let text = fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path));
+    fs::remove_file(path).ok();
+    if !status.success() {
+        bail!("The editor \"{}\" exited with {}", editor, status);
+    }
+    let text = text?;
+    let kept: Vec<&str> = text.lines().filter(|line| !line.starts_with('#')).collect();
+    Ok(Some(kept.join("\n")))
+}
+
+// Asks a yes or no question, treating anything but yes as no.
+fn confirm(question: &str) -> bool {
+    if !io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{} [y/N] ", question);
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct WydApplication {
     job_board: JobBoard,
     app_dir: PathBuf,
-    icon_url: Url,
+    icon_url: Option<Url>,
+    settings: Settings,
+    // Read-only applications never write jobs.ron, or anything but the interaction time
+    #[serde(skip)]
+    read_only: bool,
+    // While deferred, saves are only noted, and happen together later
+    #[serde(skip)]
+    defer_saves: bool,
+    #[serde(skip)]
+    save_requested: Cell<bool>,
+    // Commands turned away with a message, which `bulk` counts as failures
+    #[serde(skip)]
+    rejections: Cell<u32>,
+    // Shared with clones, like the notifier's, so it's let go once they're all done
+    #[serde(skip)]
+    state_lock: Option<Rc<StateLock>>,
 }
 
 
 impl WydApplication {
     pub fn save(&self) -> anyhow::Result<()> {
-        // Create a backup copy of the jobs file before we overwrite it
-        let copy_result = fs::copy(self.app_dir.join("jobs.ron"), self.current_backup_path());
+        self.write_board(true)
+    }
+
+    /// Saves bookkeeping from the notifier and timers, which runs far too often
+    /// to be worth a backup each time.
+    pub fn save_timers(&self) -> anyhow::Result<()> {
+        self.write_board(false)
+    }
+
+    fn write_board(&self, backup: bool) -> anyhow::Result<()> {
+        if self.read_only {
+            bail!("Attempted to save from a read-only command.");
+        }
+        if self.defer_saves {
+            self.save_requested.set(true);
+            return Ok(());
+        }
 
-        // Add any resulting errors from this copy to the log
-        if let Err(io_error) = copy_result {
-            self.append_to_log(&io_error.to_string())
+        // Create a backup copy of the jobs file before we overwrite it
+        if backup {
+            // Add any resulting errors from this copy to the log
+            if let Err(io_error) = self.backup_jobs_file() {
+                self.append_to_log(&io_error.to_string())
+            }
         }
 
         // Serialize the current job board, and write the result into jobs.ron
         let new_file_text = ser::to_string_pretty(&self.job_board, PrettyConfig::new())
             .context("Attempt to reserialize updated job list failed.")?;
-        fs::write(self.app_dir.join("jobs.ron"), new_file_text)
+        write_atomically(&self.app_dir.join("jobs.ron"), new_file_text.as_bytes())
             .context("Failed to write updated job list.")?;
 
+        // Failing to write the dashboard is logged, but never fails the save
+        if self.settings.auto_html {
+            self.write_html();
+        }
+
+        Ok(())
+    }
+
+    pub fn defer_saves(&mut self) {
+        self.defer_saves = true;
+    }
+
+    /// Tells the user why a command couldn't run. This isn't an error,
+    /// but it's counted, so `bulk` can report the line as failed.
+    pub fn reject(&self, message: impl Display) {
+        eprintln!("{}", message);
+        self.rejections.set(self.rejections.get() + 1);
+    }
+
+    pub fn rejection_count(&self) -> u32 {
+        self.rejections.get()
+    }
+
+    /// Keeps `state_lock` until the app is dropped, apart from while waiting on the user.
+    pub fn hold_state_lock(&mut self, state_lock: StateLock) {
+        self.state_lock = Some(Rc::new(state_lock));
+    }
+
+    // Runs `prompt` with the state lock let go, so an open editor or an unanswered
+    // question doesn't hold up other commands, then takes the lock back and reloads
+    // the board. Returns None, having said why, if jobs were added, removed or
+    // reordered meanwhile, since whatever was chosen may not be where it was.
+    fn unlocked<T>(&mut self, prompt: impl FnOnce() -> T) -> Option<T> {
+        // Deferred changes aren't on disk yet, so reloading would lose them
+        if self.state_lock.is_none() || self.defer_saves {
+            return Some(prompt());
+        }
+        self.state_lock = None;
+        let answer = prompt();
+        let reloaded = StateLock::acquire(&self.app_dir).and_then(|state_lock| {
+            self.hold_state_lock(state_lock);
+            JobBoard::load_read_only(&self.app_dir)
+        });
+        let mut board = match reloaded {
+            Ok(board) => board,
+            Err(error) => {
+                self.reject(format!("{:#}", error));
+                return None;
+            }
+        };
+        board.migrate();
+        let layout = |board: &JobBoard| -> Vec<String> {
+            let active = board.active_stack.iter().map(|job| job.id.clone());
+            let suspended = board.suspended_stacks.iter().map(|stack| stack.id().to_owned());
+            active.chain(std::iter::once(String::new())).chain(suspended).collect()
+        };
+        if layout(&board) != layout(&self.job_board) {
+            self.reject("The board changed while waiting for you, so nothing was done. Try again.");
+            return None;
+        }
+        board.last_interaction = board.last_interaction.max(self.job_board.last_interaction);
+        self.job_board = board;
+        Some(answer)
+    }
+
+    pub fn finish_deferred_saves(&mut self) -> anyhow::Result<()> {
+        self.defer_saves = false;
+        if self.save_requested.replace(false) {
+            self.save()?;
+        }
         Ok(())
     }
 
     pub fn load(app_dir: PathBuf) -> anyhow::Result<WydApplication> {
-        let job_board = JobBoard::load(&app_dir);
+        let mut job_board = JobBoard::load(&app_dir);
+        let migrated_from = job_board.migrate();
+        job_board.forget_missing_dependencies();
+        let settings = Settings::load(&app_dir)?;
         let icon_url = match Url::from_file_path(app_dir.join("wyd-icon.png")) {
             Ok(url) => url,
             Err(()) => bail!("Failed to create file url for icon."),
         };
+        let app = WydApplication {
+            app_dir,
+            job_board,
+            icon_url: Some(icon_url),
+            settings,
+            read_only: false,
+            defer_saves: false,
+            save_requested: Cell::new(false),
+            rejections: Cell::new(0),
+            state_lock: None,
+        };
+        // Rewrite the file once, so the upgrade doesn't run on every load
+        if let Some(old_version) = migrated_from {
+            app.append_to_log(&format!(
+                "Upgraded jobs.ron from schema version {} to {}\n",
+                old_version,
+                app.job_board.schema_version
+            ));
+            app.save().context("Unable to save upgraded job list.")?;
+        }
+        Ok(app)
+    }
+
+    /// A lighter load for commands that only read the board, so frequent
+    /// polling never opens jobs.ron for writing or upgrades it.
+    pub fn load_read_only(app_dir: PathBuf) -> anyhow::Result<WydApplication> {
+        let mut job_board = JobBoard::load_read_only(&app_dir)?;
+        if job_board.migrate().is_some() {
+            // Ids made up while loading an old board would change on every run,
+            // so the upgrade is saved once, like any other load would
+            let _lock = StateLock::acquire(&app_dir)?;
+            let mut app = Self::load(app_dir)?;
+            app.read_only = true;
+            return Ok(app);
+        }
+        job_board.forget_missing_dependencies();
+        let settings = Settings::load(&app_dir)?;
         Ok(WydApplication {
             app_dir,
             job_board,
-            icon_url,
+            icon_url: None,
+            settings,
+            read_only: true,
+            defer_saves: false,
+            save_requested: Cell::new(false),
+            rejections: Cell::new(0),
+            state_lock: None,
         })
     }
 
+    // Picks one of several (index, label) candidates matching a pattern.
+    // Asks the user when the choice is ambiguous and stdin is interactive,
+    // and otherwise lists the candidates instead of guessing.
+    fn choose_candidate(&mut self, candidates: &[(usize, String)], first: bool) -> Option<usize> {
+        match candidates {
+            [] => return None,
+            [(index, _label)] => return Some(*index),
+            _ if first => return Some(candidates[0].0),
+            _ => {}
+        }
+
+        if !io::stdin().is_terminal() {
+            self.reject("More than one task matches. Use a more specific pattern, or --first:");
+            for (_index, label) in candidates {
+                eprintln!("    {}", label);
+            }
+            return None;
+        }
+
+        let answer = self.unlocked(|| {
+            println!("More than one task matches:");
+            for (number, (_index, label)) in candidates.iter().enumerate() {
+                println!("{:>4}) {}", number + 1, label);
+            }
+            print!("Choose a task [1-{}], or press enter to cancel: ", candidates.len());
+            io::stdout().flush().ok()?;
+            let mut answer = String::new();
+            io::stdin().read_line(&mut answer).ok()?;
+            Some(answer)
+        })??;
+        match answer.trim().parse::<usize>() {
+            Ok(number) if number >= 1 && number <= candidates.len() => {
+                Some(candidates[number - 1].0)
+            }
+            _ => {
+                self.reject("No task chosen.");
+                None
+            }
+        }
+    }
+
     fn print(&self, message: &str) {
         self.append_to_log(&(message.to_owned() + "\n"));
         println!("{}", message.trim());
     }
 
+    // The line to log for a job starting or finishing, which is `default_line`
+    // unless the user set a `log_format`.
+    fn job_log_line(
+        &self,
+        default_line: String,
+        verb: &str,
+        job: &Job,
+        elapsed: StdDuration,
+    ) -> String {
+        match &self.settings.log_format {
+            Some(log_format) => settings::render_log_format(
+                log_format,
+                &settings::LogFields {
+                    time: Utc::now(),
+                    timezone: self.settings.timezone,
+                    indent: &self.get_indent(),
+                    verb,
+                    label: &job.label,
+                    elapsed,
+                },
+            ),
+            None => default_line,
+        }
+    }
+
     fn get_indent(&self) -> String {
         let mut output = String::new();
         for _ in &self.job_board.active_stack {
@@ -122,18 +717,78 @@ impl WydApplication {
         output
     }
 
+    /// `date` in the configured timezone, or the system's when none is set.
+    pub fn local_time(&self, date: DateTime<Utc>) -> DateTime<FixedOffset> {
+        settings::in_timezone(date, self.settings.timezone)
+    }
+
+    /// Today's date in the configured timezone.
+    pub fn today(&self) -> NaiveDate {
+        self.local_time(Utc::now()).naive_local().date()
+    }
+
     fn current_log_path(&self) -> PathBuf {
-        let date = Local::now();
-        let log_file_name = format!("{}", date.format("wyd-%F.log"));
+        self.log_path(self.today())
+    }
+
+    fn log_path(&self, day: NaiveDate) -> PathBuf {
+        let log_file_name = format!("{}", day.format("wyd-%F.log"));
         self.app_dir.join(log_file_name)
     }
 
     fn current_backup_path(&self) -> PathBuf {
-        let date = Local::now();
-        let log_file_name = format!("{}", date.format("jobs-archive-%F.ron"));
+        let date = self.local_time(Utc::now());
+        let log_file_name = format!("{}", date.format("jobs-archive-%F-%H%M%S.ron"));
         self.app_dir.join(log_file_name)
     }
 
+    fn backup_paths(&self) -> std::io::Result<Vec<PathBuf>> {
+        let mut backups = Vec::new();
+        for entry in fs::read_dir(&self.app_dir)? {
+            let path = entry?.path();
+            let is_backup = match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => name.starts_with("jobs-archive-") && name.ends_with(".ron"),
+                None => false,
+            };
+            if is_backup {
+                let modified = fs::metadata(&path)?.modified()?;
+                backups.push((modified, path));
+            }
+        }
+        // Oldest backups first
+        backups.sort();
+        Ok(backups.into_iter().map(|(_modified, path)| path).collect())
+    }
+
+    fn backup_jobs_file(&self) -> std::io::Result<()> {
+        let current_contents = match fs::read(self.app_dir.join("jobs.ron")) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error),
+        };
+
+        let mut backups = self.backup_paths()?;
+
+        // Skip the copy if nothing changed since the latest backup
+        if let Some(latest_backup) = backups.last() {
+            if fs::read(latest_backup)? == current_contents {
+                return Ok(());
+            }
+        }
+
+        let backup_path = self.current_backup_path();
+        fs::write(&backup_path, &current_contents)?;
+        if !backups.contains(&backup_path) {
+            backups.push(backup_path);
+        }
+
+        // Keep only the most recent backups
+        while backups.len() > self.settings.backup_count.max(1) {
+            fs::remove_file(backups.remove(0))?;
+        }
+        Ok(())
+    }
+
     fn append_to_log(&self, text: &str) {
         let log_path = self.current_log_path();
 
@@ -147,35 +802,57 @@ impl WydApplication {
             .expect(&format!("Failed to write to log file at {:?}", log_path));
     }
 
-    pub fn create_suspended_job(
-        &mut self,
-        label: String,
-        reason: String,
-        timer: Option<DateTime<Utc>>,
-    ) {
-        let job = Job {
-            label,
-            begin_date: Utc::now(),
-            timebox: None,
-            last_notification: None,
+    pub fn create_suspended_job(&mut self, label: String, options: SuspendOptions) {
+        let job = Job::new(label, Utc::now());
+        self.job_board.add_suspended_stack(options.into_stack(vec![job]));
+    }
+
+    /// Finds the id of any job on the board, active or suspended, by `#id` or label,
+    /// asking which one if several match.
+    pub fn choose_job_id(&mut self, pattern: &str, purpose: &str) -> Option<String> {
+        let jobs: Vec<(String, String)> = self
+            .job_board
+            .all_jobs()
+            .filter(|job| match pattern.strip_prefix('#') {
+                Some(id) => job.id == id,
+                None => job.label.contains(pattern),
+            })
+            .map(|job| (job.id.clone(), job.label.clone()))
+            .collect();
+        if jobs.is_empty() {
+            self.reject(format!("No task matches \"{}\" to {}.", pattern, purpose));
+            return None;
+        }
+        let candidates: Vec<_> = jobs.iter().map(|(_, label)| label.clone()).enumerate().collect();
+        let index = self.choose_candidate(&candidates, false)?;
+        Some(jobs[index].0.clone())
+    }
+
+    /// Asks for a new job's label in an editor, with any further lines as notes.
+    /// Returns None if there's no editor to ask with, or nothing was written.
+    pub fn compose_job(&mut self) -> anyhow::Result<Option<(String, Vec<String>)>> {
+        let instructions = "Write the task on the first line, and any notes on the lines after it.\n\
+            An empty task cancels the push.";
+        let edit_path = self.app_dir.join("PUSH_EDITMSG");
+        let text = match self.unlocked(|| compose_in_editor(&edit_path, "", instructions)) {
+            Some(text) => text?,
+            None => return Ok(None),
         };
-        let new_stack = SuspendedStack {
-            data: vec![job],
-            reason,
-            date_suspended: Utc::now(),
-            timer,
-            last_notifiaction: None,
+        let text = match text {
+            Some(text) => text,
+            None => return Ok(None),
+        };
+        let mut lines = text.lines().map(str::trim).skip_while(|line| line.is_empty());
+        let label = match lines.next() {
+            Some(label) => label.to_owned(),
+            None => return Ok(None),
         };
-        self.job_board.add_suspended_stack(new_stack);
+        let notes = lines.filter(|line| !line.is_empty()).map(str::to_owned).collect();
+        Ok(Some((label, notes)))
     }
 
-    pub fn create_job(
-        &mut self,
-        label: String,
-        timebox: Option<StdDuration>,
-        retro: Option<StdDuration>,
-    ) -> anyhow::Result<()> {
-        let begin_date = if let Some(retro) = retro {
+    pub fn create_job(&mut self, label: String, options: PushOptions) -> anyhow::Result<()> {
+        let begin_date = if let Some(retro) = options.retro {
             let dur =
                 Duration::from_std(retro).expect("Unable to convert duration to chrono format.");
             Utc::now()
@@ -190,7 +867,7 @@ impl WydApplication {
         }) = self.job_board.active_stack.last()
         {
             // Timeboxed tasks cannot have subtasks
-            eprintln!(
+            self.reject(
                 "Current job has a timebox. \
                 Finish the task or remove the timebox before \
                 Creating a sub task."
@@ -198,22 +875,175 @@ impl WydApplication {
             return Ok(());
         }
 
+        let blocked_by = match options.depends_on.as_deref() {
+            Some(pattern) => match self.choose_job_id(pattern, "depend on") {
+                Some(id) => Some(id),
+                None => return Ok(()),
+            },
+            None => None,
+        };
+
+        let mut tags = options.tags;
+        if let Some(context) = &self.job_board.context {
+            if !tags.contains(context) {
+                tags.push(context.clone());
+            }
+        }
+
         let job = Job {
-            label,
-            begin_date,
-            timebox,
-            last_notification: None,
+            timebox: options.timebox,
+            blocked_by,
+            reminder_policy: options.reminder_policy,
+            tags,
+            links: options.links,
+            quiet_until: options.quiet_until,
+            priority: options.priority,
+            // Whole seconds, like the start date once it's saved
+            paused_since: if options.start_paused {
+                Some(begin_date.with_nanosecond(0).unwrap_or(begin_date))
+            } else {
+                None
+            },
+            ..Job::new(label, begin_date)
         };
 
-        let mut log_line = String::new();
-        log_line.push_str(&self.get_indent());
-        log_line.push_str(&format!("{}", job));
-        self.print(&log_line);
+        let mut display_line = String::new();
+        display_line.push_str(&self.get_indent());
+        display_line.push_str(&format!("{}", job));
+        let verb = if options.start_paused { "Queued" } else { "Started" };
+        let log_line = self.job_log_line(display_line.clone(), verb, &job, StdDuration::ZERO);
+        self.append_to_log(&(log_line + "\n"));
+        println!("{}", display_line.trim());
         self.job_board.push(job);
         self.save().context("Unable to save after job creation.")?;
+        for note in options.notes {
+            self.add_log_note(note, None);
+        }
         Ok(())
     }
 
+    // How long past jobs like this one took, oldest first. Jobs with the same
+    // label count as similar, or failing that, jobs with exactly the same tags.
+    fn similar_completions(
+        &self,
+        label: &str,
+        tags: &[String],
+    ) -> anyhow::Result<Vec<(String, StdDuration)>> {
+        let records: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        let finished = || records.iter().filter(|record| !record.cancelled);
+        let mut similar: Vec<&CompletionRecord> = finished()
+            .filter(|record| record.label.trim().eq_ignore_ascii_case(label.trim()))
+            .collect();
+        if similar.is_empty() && !tags.is_empty() {
+            let mut tags = tags.to_vec();
+            tags.sort();
+            similar = finished()
+                .filter(|record| {
+                    let mut record_tags = record.tags.clone();
+                    record_tags.sort();
+                    record_tags == tags
+                })
+                .collect();
+        }
+        Ok(similar
+            .into_iter()
+            .filter_map(|record| {
+                let took = record.end_date.signed_duration_since(record.begin_date);
+                Some((record.label.clone(), took.to_std().ok()?))
+            })
+            .collect())
+    }
+
+    /// Pushes a job, suggesting a timebox from how long similar jobs took.
+    /// With `apply_estimate`, the median of those durations becomes the timebox.
+    pub fn create_job_from_history(
+        &mut self,
+        label: String,
+        mut options: PushOptions,
+        apply_estimate: bool,
+    ) -> anyhow::Result<()> {
+        if options.timebox.is_some() {
+            return self.create_job(label, options);
+        }
+        // Whole minutes read better as a timebox than a precise duration
+        let round = |duration: StdDuration| {
+            StdDuration::from_secs(((duration.as_secs() + 30) / 60).max(1) * 60)
+        };
+        // Past completions only inform a suggestion, so failing to read them can't stop the push
+        let similar = self
+            .similar_completions(&label, &options.tags)
+            .unwrap_or_else(|error| {
+                eprintln!("Couldn't read past completions: {:#}", error);
+                Vec::new()
+            });
+        if !apply_estimate {
+            let depth = self.job_board.active_stack.len();
+            self.create_job(label, options)?;
+            if self.job_board.active_stack.len() == depth {
+                // The push was refused, so there's nothing to timebox
+                return Ok(());
+            }
+            if let Some((similar_label, took)) = similar.last() {
+                let took = humantime::format_duration(round(*took));
+                println!(
+                    "Similar task \"{}\" took ~{} last time; use --timebox {}?",
+                    similar_label, took, took
+                );
+            }
+            return Ok(());
+        }
+
+        let mut durations: Vec<StdDuration> = similar.iter().map(|(_, took)| *took).collect();
+        durations.sort();
+        match durations.get(durations.len() / 2) {
+            Some(median) => {
+                let timebox = round(*median);
+                println!(
+                    "Estimated {} from {} similar finished {}.",
+                    humantime::format_duration(timebox),
+                    durations.len(),
+                    if durations.len() == 1 { "task" } else { "tasks" }
+                );
+                options.timebox = Some(timebox);
+            }
+            None => println!("No similar finished tasks to estimate from, so no timebox was set."),
+        }
+        self.create_job(label, options)
+    }
+
+    /// Pushes each step as a subtask of the one after it, so the first
+    /// step ends up on top and `done` works through them in order.
+    pub fn create_checklist(
+        &mut self,
+        steps: Vec<String>,
+        options: PushOptions,
+    ) -> anyhow::Result<()> {
+        if let Some(Job {
+            timebox: Some(_), ..
+        }) = self.job_board.active_stack.last()
+        {
+            self.reject(
+                "Current job has a timebox. \
+                Finish the task or remove the timebox before \
+                creating a checklist."
+            );
+            return Ok(());
+        }
+        // Inside `bulk`, saves are already deferred, and `bulk` finishes them
+        let already_deferred = self.defer_saves;
+        self.defer_saves();
+        for step in steps.into_iter().rev() {
+            self.create_job(step, options.clone())?;
+        }
+        print!("{}", self.get_summary());
+        if already_deferred {
+            return Ok(());
+        }
+        self.finish_deferred_saves()
+            .context("Unable to save after creating checklist.")
+    }
+
     fn indent(&self, text: impl Display) -> String {
         let mut log_line = String::new();
         log_line.push_str(&self.get_indent());
@@ -222,54 +1052,363 @@ impl WydApplication {
     }
 
     fn timestamp(&self, text: impl Display) -> String {
-        let timestamp = Local::now().format("%r");
+        let timestamp = self.local_time(Utc::now()).format("%r");
         format!("{}: {}", timestamp, text)
     }
 
-    fn lock_path(&self) -> PathBuf {
-        self.app_dir.join(".notifier")
+    fn focus_records_path(&self) -> PathBuf {
+        self.app_dir.join("focus-records.ron")
     }
 
-    pub fn update_timers(&mut self) -> anyhow::Result<TimerState> {
-        for job in &mut self.job_board.active_stack {
-            if job.timebox_expired() {
-                if !should_notify(&job.last_notification) {
-                    continue;
-                }
-                
-                job.last_notification = Some(Utc::now());
-                return Ok(TimerState{ send_alarm: true, needs_save: true});
-            }
-        }
-
-        for stack in &mut self.job_board.suspended_stacks {
-            let timer_exhausted = match stack.timer {
-                Some(timer) => timer < Utc::now(),
-                None => false,
-            };
-            if !timer_exhausted {
-                continue;
-            }
-            if !should_notify(&stack.last_notifiaction) {
-                continue;
-            }
-            stack.last_notifiaction = Some(Utc::now());
-            return Ok(TimerState{ send_alarm: true, needs_save: true});
-        }
+    fn goals_path(&self) -> PathBuf {
+        self.app_dir.join("goals.ron")
+    }
 
-        let slack_date = match self.job_board.work_state {
-            WorkState::Off => None,
-            WorkState::Working => Some(Utc::now()),
-            WorkState::SlackingSince(date) => Some(date),
+    fn goal_progress(&self, day: NaiveDate) -> anyhow::Result<GoalProgress> {
+        let (start, end) = match stats::day_bounds(day, self.settings.timezone) {
+            Some((start, end)) => (start, end.min(Utc::now())),
+            None => bail!("Unable to determine the bounds of {}", day),
         };
+        let completions: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        let tasks_done = completions
+            .iter()
+            .filter(|record| !record.cancelled && record.end_date >= start && record.end_date < end)
+            .count() as u32;
+        let mut focus_records: Vec<FocusRecord> =
+            history::read_records(&self.focus_records_path())?;
+        focus_records.sort_by_key(|record| record.date);
+        let (focused, _slacking) = stats::focus_totals(&focus_records, start, end);
+        Ok(GoalProgress {
+            tasks_done,
+            focused,
+        })
+    }
 
-        if let Some(slack_date) = slack_date {
-            let mut timer_state = TimerState{ send_alarm: false, needs_save: false};
-            let is_slacking = self.job_board.active_stack.iter().all(|job| {
-                job.timebox.is_none()
-            });
-            let new_work_state = if is_slacking {
-                let now = Utc::now();
+    pub fn set_goal(&self, kind: GoalKind, value: &str) -> anyhow::Result<()> {
+        let path = self.goals_path();
+        let mut goals = goals::load(&path)?;
+        let today = self.today();
+        goals.entry(today).or_default().set(kind, value)?;
+        goals::save(&path, &goals)?;
+        self.print_goal_progress()
+    }
+
+    pub fn clear_goals(&self) -> anyhow::Result<()> {
+        let path = self.goals_path();
+        let mut goals = goals::load(&path)?;
+        if goals.remove(&self.today()).is_none() {
+            println!("No goals are set for today.");
+            return Ok(());
+        }
+        goals::save(&path, &goals)?;
+        println!("Cleared today's goals.");
+        Ok(())
+    }
+
+    /// Describes progress towards today's goals, if any are set.
+    pub fn goal_summary(&self) -> anyhow::Result<Option<String>> {
+        let today = self.today();
+        let day_goals = match goals::load(&self.goals_path())?.remove(&today) {
+            Some(day_goals) if !day_goals.is_empty() => day_goals,
+            _ => return Ok(None),
+        };
+        let progress = self.goal_progress(today)?;
+        Ok(Some(format!("Goals: {}", day_goals.summary(&progress))))
+    }
+
+    pub fn print_goal_progress(&self) -> anyhow::Result<()> {
+        match self.goal_summary()? {
+            Some(summary) => println!("{}", summary),
+            None => println!("No goals are set for today. Set one with e.g. `wyd goal set tasks 5`."),
+        }
+        Ok(())
+    }
+
+    // Announcements for goals met since the last check, each made only once.
+    fn celebrate_goals(&self) -> Vec<String> {
+        let path = self.goals_path();
+        let today = self.today();
+        let mut goals = match goals::load(&path) {
+            Ok(goals) => goals,
+            Err(_) => return Vec::new(),
+        };
+        let day_goals: &mut DayGoals = match goals.get_mut(&today) {
+            // Skips reading the history when there's nothing left to celebrate
+            Some(day_goals) if day_goals.has_uncelebrated() => day_goals,
+            _ => return Vec::new(),
+        };
+        let progress = match self.goal_progress(today) {
+            Ok(progress) => progress,
+            Err(_) => return Vec::new(),
+        };
+        let newly_met = day_goals.uncelebrated(&progress);
+        if newly_met.is_empty() {
+            return Vec::new();
+        }
+        let messages = newly_met
+            .iter()
+            .map(|kind| format!("🎉 Daily {} goal met! {}", kind, day_goals.summary(&progress)))
+            .collect();
+        day_goals.celebrated.extend(newly_met);
+        if let Err(error) = goals::save(&path, &goals) {
+            self.append_to_log(&format!("{:#}\n", error));
+        }
+        messages
+    }
+
+    fn notes_path(&self) -> PathBuf {
+        self.app_dir.join("notes.ron")
+    }
+
+    fn completion_records_path(&self) -> PathBuf {
+        self.app_dir.join("history.ron")
+    }
+
+    fn record_completion(
+        &self,
+        job: &Job,
+        cancelled: bool,
+        time_tag: Option<String>,
+        reason: Option<String>,
+    ) {
+        let record = CompletionRecord {
+            id: job.id.clone(),
+            label: job.label.clone(),
+            begin_date: job.begin_date,
+            end_date: Utc::now(),
+            depth: self.job_board.active_stack.len(),
+            cancelled,
+            tags: job.tags.clone(),
+            estimate: job.estimate,
+            timebox: job.timebox,
+            time_tag,
+            reason,
+        };
+        if let Err(error) = history::append_record(&self.completion_records_path(), &record) {
+            self.append_to_log(&format!("{:#}\n", error));
+        }
+    }
+
+    /// Adds a finished job to the history without it ever being on the board,
+    /// for work that was done without pushing it first.
+    pub fn log_work(
+        &self,
+        label: String,
+        duration: StdDuration,
+        tags: Vec<String>,
+    ) -> anyhow::Result<()> {
+        let end_date = Utc::now();
+        let begin_date = Duration::from_std(duration)
+            .ok()
+            .and_then(|duration| end_date.checked_sub_signed(duration))
+            .context("That's too long ago to log.")?;
+        let record = CompletionRecord {
+            id: new_id(),
+            label,
+            begin_date,
+            end_date,
+            depth: 0,
+            cancelled: false,
+            tags,
+            estimate: None,
+            timebox: None,
+            time_tag: None,
+            reason: None,
+        };
+        history::append_record(&self.completion_records_path(), &record)?;
+        let display_line = format!(
+            "Logged job \"{}\" (time elapsed: {})",
+            record.label,
+            humantime::format_duration(stats::round_up(duration, self.settings.time_rounding))
+        );
+        self.append_to_log(&format!("{}\n", self.timestamp(&display_line)));
+        println!("{}", display_line);
+        for message in self.celebrate_goals() {
+            self.append_to_log(&format!("{}\n", self.timestamp(&message)));
+            println!("{}", message);
+        }
+        Ok(())
+    }
+
+    fn record_focus_change(&self, old_state: WorkState, new_state: WorkState) {
+        let new_state = FocusState::from(new_state);
+        if FocusState::from(old_state) == new_state {
+            return;
+        }
+        let record = FocusRecord {
+            date: Utc::now(),
+            state: new_state,
+        };
+        if let Err(error) = history::append_record(&self.focus_records_path(), &record) {
+            self.append_to_log(&format!("{:#}\n", error));
+        }
+    }
+
+    fn lock_path(&self) -> PathBuf {
+        self.app_dir.join(".notifier")
+    }
+
+    fn interaction_path(&self) -> PathBuf {
+        self.app_dir.join(".last-interaction")
+    }
+
+    /// Notes that the user just ran a command, for `auto_suspend_after`.
+    pub fn record_interaction(&mut self) {
+        self.record_interaction_at(Utc::now());
+    }
+
+    // Read-only commands can't save the board, so they leave the time in a file of its own
+    fn record_interaction_at(&mut self, now: DateTime<Utc>) {
+        if self.read_only {
+            // A lost write only makes an auto-suspend come early, so it isn't worth failing over
+            write_atomically(&self.interaction_path(), now.to_rfc3339().as_bytes()).ok();
+        } else {
+            self.job_board.last_interaction = Some(now);
+        }
+    }
+
+    // The later of the board's interaction time and the one left by a read-only command
+    fn last_interaction(&self) -> Option<DateTime<Utc>> {
+        let read_only_interaction = fs::read_to_string(self.interaction_path())
+            .ok()
+            .and_then(|text| DateTime::parse_from_rfc3339(text.trim()).ok())
+            .map(|date| date.with_timezone(&Utc));
+        self.job_board.last_interaction.max(read_only_interaction)
+    }
+
+    // Suspends the whole active stack if it's been left alone for too long,
+    // returning the label of its root job.
+    fn auto_suspend_inactive(&mut self, now: DateTime<Utc>) -> Option<String> {
+        let limit = Duration::from_std(self.settings.auto_suspend_after?).ok()?;
+        let top_job = self.job_board.active_stack.last()?;
+        let last_activity = match self.last_interaction() {
+            Some(interaction) => interaction.max(top_job.begin_date),
+            None => top_job.begin_date,
+        };
+        if now.signed_duration_since(last_activity) <= limit {
+            return None;
+        }
+        let label = self.job_board.active_stack[0].label.clone();
+        let options = SuspendOptions {
+            reason: "auto-suspended (inactive)".to_owned(),
+            ..SuspendOptions::default()
+        };
+        self.job_board.suspend_at(0, options).ok()?;
+        self.append_to_log(&format!("Auto-suspended \"{}\" after inactivity\n", label));
+        Some(label)
+    }
+
+    pub fn update_timers(&mut self) -> anyhow::Result<TimerState> {
+        self.update_timers_at(Utc::now())
+    }
+
+    fn update_timers_at(&mut self, now: DateTime<Utc>) -> anyhow::Result<TimerState> {
+        // Everything that comes due together shares a single alarm
+        let mut reminders = Vec::new();
+        if let Some(label) = self.auto_suspend_inactive(now) {
+            reminders.push(format!("{} was suspended after inactivity", label));
+        }
+        for message in self.celebrate_goals() {
+            self.append_to_log(&format!("{}\n", self.timestamp(&message)));
+            reminders.push(message);
+        }
+        for job in &mut self.job_board.active_stack {
+            let expired = job.timebox_expiry().is_some_and(|expiry| expiry <= now);
+            if !expired {
+                continue;
+            }
+            if matches!(job.quiet_until, Some(quiet_until) if now < quiet_until) {
+                continue;
+            }
+            if !should_notify(
+                &job.last_notification,
+                job.notification_count,
+                job.reminder_policy,
+                now,
+            ) {
+                continue;
+            }
+            job.last_notification = Some(now);
+            job.notification_count += 1;
+            reminders.push(job.label.clone());
+        }
+
+        for stack in &mut self.job_board.suspended_stacks {
+            if stack.silent {
+                continue;
+            }
+            let timer_exhausted = match stack.timer {
+                Some(timer) => timer < now,
+                None => false,
+            };
+            if !timer_exhausted {
+                continue;
+            }
+            if !should_notify(
+                &stack.last_notification,
+                stack.notification_count,
+                stack.reminder_policy,
+                now,
+            ) {
+                continue;
+            }
+            stack.last_notification = Some(now);
+            stack.notification_count += 1;
+            reminders.push(stack.data[0].label.clone());
+        }
+
+        if !reminders.is_empty() {
+            return Ok(TimerState{ send_alarm: true, needs_save: true, reminders});
+        }
+
+        // Outside of work hours, work mode is held off unless it was deliberately
+        // started there. It stays on, so it picks up again when the hours come back.
+        if self.job_board.work_forced && self.in_work_hours(now) {
+            self.job_board.work_forced = false;
+            return Ok(TimerState{ send_alarm: false, needs_save: true, reminders});
+        }
+        let work_mode_on = self.job_board.work_state != WorkState::Off;
+        let held_off = work_mode_on && !self.job_board.work_forced && !self.in_work_hours(now);
+        if held_off != self.job_board.off_hours {
+            self.job_board.off_hours = held_off;
+            if held_off {
+                self.record_focus_change(self.job_board.work_state, WorkState::Off);
+            } else {
+                // Slacking from before the break in hours doesn't count against the new day
+                if let WorkState::SlackingSince(_) = self.job_board.work_state {
+                    self.job_board.work_state = WorkState::Working;
+                }
+                self.record_focus_change(WorkState::Off, self.job_board.work_state);
+            }
+            return Ok(TimerState{ send_alarm: false, needs_save: true, reminders});
+        }
+        if held_off {
+            return Ok(TimerState{ send_alarm: false, needs_save: false, reminders});
+        }
+
+        // A break ends by itself, and work mode carries on where it left off
+        if let WorkState::BreakUntil(break_end) = self.job_board.work_state {
+            if now < break_end {
+                return Ok(TimerState{ send_alarm: false, needs_save: false, reminders: Vec::new()});
+            }
+            self.record_focus_change(self.job_board.work_state, WorkState::Working);
+            self.job_board.work_state = WorkState::Working;
+            let message = "Your break is over".to_owned();
+            self.append_to_log(&format!("{}\n", self.timestamp(&message)));
+            return Ok(TimerState{ send_alarm: true, needs_save: true, reminders: vec![message]});
+        }
+
+        let slack_date = match self.job_board.work_state {
+            WorkState::Off | WorkState::BreakUntil(_) => None,
+            WorkState::Working => Some(now),
+            WorkState::SlackingSince(date) => Some(date),
+        };
+
+        if let Some(slack_date) = slack_date {
+            let mut timer_state = TimerState{ send_alarm: false, needs_save: false, reminders: Vec::new()};
+            let is_slacking = !self.job_board.active_stack.iter().any(Job::counts_as_focus);
+            let new_work_state = if is_slacking {
                 if now.signed_duration_since(slack_date).num_seconds() > 5*60 {
                     timer_state.send_alarm = true;
                     WorkState::SlackingSince(now)
@@ -282,6 +1421,7 @@ impl WydApplication {
             };
 
             if new_work_state != self.job_board.work_state {
+                self.record_focus_change(self.job_board.work_state, new_work_state);
                 self.job_board.work_state = new_work_state;
                 timer_state.needs_save = true;
             }
@@ -289,7 +1429,7 @@ impl WydApplication {
             return Ok(timer_state);
         }
 
-        return Ok(TimerState{ send_alarm: false, needs_save: false});    
+        return Ok(TimerState{ send_alarm: false, needs_save: false, reminders: Vec::new()});
     }
 
     // CLI methods:
@@ -315,13 +1455,24 @@ impl WydApplication {
                     break;
                 }
             }
+            // A command is busy with the board, so check again next time around
+            let state_lock = match StateLock::try_acquire(&app_dir) {
+                Some(state_lock) => state_lock,
+                None => {
+                    std::thread::sleep(std::time::Duration::from_secs(1));
+                    continue;
+                }
+            };
             self = WydApplication::load(app_dir).context("Failed to deserialize application state")?;
             let timer_state = self.update_timers()?;
             if timer_state.needs_save {
-                self.save().context("Unable to save from reminder thread.")?;
+                self.save_timers().context("Unable to save from reminder thread.")?;
             }
+            drop(state_lock);
             if timer_state.send_alarm {
-                play_alarm().context("Unable to play alarm sound")?;
+                for problem in self.sound_alarm(&timer_state.reminders) {
+                    self.append_to_log(&format!("{}\n", problem));
+                }
             }
             app_dir = self.app_dir;
             std::thread::sleep(std::time::Duration::from_secs(1));
@@ -329,6 +1480,49 @@ impl WydApplication {
         Ok(())
     }
 
+    /// Speaks the reminders or plays the alarm, falling back to the terminal bell.
+    /// Returns a description of anything that didn't work along the way.
+    fn sound_alarm(&self, reminders: &[String]) -> Vec<String> {
+        let mut problems = Vec::new();
+        let _alarm_guard = AlarmGuard::acquire(&self.app_dir);
+        if self.settings.voice_reminders && !reminders.is_empty() {
+            let speech = format!("Reminder: {}", reminders.join(", "));
+            match speak(&speech) {
+                Ok(()) => return problems,
+                Err(error) => problems.push(format!(
+                    "Unable to speak reminder, playing alarm instead: {:#}",
+                    error
+                )),
+            }
+        }
+        // A missing sound device shouldn't take the notifier down with it
+        if let Err(error) = play_alarm(self.settings.volume) {
+            problems.push(format!(
+                "Unable to play alarm sound, ringing the terminal bell instead: {:#}",
+                error
+            ));
+            print!("\x07");
+            io::stdout().flush().ok();
+        }
+        problems
+    }
+
+    /// Sounds a reminder for the current job right away, whatever its timers say.
+    /// The notifier's own schedule is left alone.
+    pub fn nudge(&self) {
+        let job = match self.job_board.active_stack.last() {
+            Some(job) => job,
+            None => {
+                self.reject("There's no current task to be reminded of.");
+                return;
+            }
+        };
+        println!("Nudging: {}", job.label);
+        for problem in self.sound_alarm(std::slice::from_ref(&job.label)) {
+            eprintln!("{}", problem);
+        }
+    }
+
     pub fn spawn_notifier(&self) {
         let lock_path = self.lock_path();
         // Default usage - spawn the notifier process
@@ -348,31 +1542,53 @@ impl WydApplication {
             .arg("notifier")
             .arg("--become")
             .arg(ron::to_string(&id).unwrap())
+            // Keeps the notifier on this data directory, however it was chosen
+            .arg("--app-dir")
+            .arg(&self.app_dir)
             .spawn()
             .expect("Unable to spawn notifier process.");
     }
 
-    pub fn ls_job_board(&mut self) {
+    pub fn ls_job_board(&mut self, sort: StackSort, limit: Option<usize>) {
         self.job_board.sort_suspended_stacks();
         let main_summary = self.job_board.get_summary();
-        let suspended_summary = self.job_board.suspended_stack_summary();
-        print!(
-            "Suspended jobs:\n\n{}\n\nMain jobs:\n\n{}\n",
-            suspended_summary, main_summary
-        )
+        let suspended_summary = self.job_board.sorted_stack_summary(sort, false, limit);
+        let someday_summary = self.job_board.sorted_stack_summary(sort, true, limit);
+        print!("Suspended jobs:\n\n{}\n\n", suspended_summary);
+        if !someday_summary.is_empty() {
+            print!("Someday:\n\n{}\n\n", someday_summary);
+        }
+        print!("Main jobs:\n\n{}\n", main_summary)
     }
 
-    pub fn suspend_current_job(&mut self, reason: String, timer: Option<DateTime<Utc>>) {
-        if self.job_board.suspend_current(reason, timer).is_ok() {
+    pub fn print_json_listing(&mut self) -> anyhow::Result<()> {
+        self.job_board.sort_suspended_stacks();
+        println!("{}", export::json_listing(&self.job_board)?);
+        Ok(())
+    }
+
+    pub fn suspend_current_job(&mut self, options: SuspendOptions) {
+        if self.job_board.suspend_current(options).is_ok() {
             println!("Job suspended.");
         } else {
             println!("No job to suspend.")
         }
     }
 
-    pub fn apply_timebox(&mut self, timebox: Option<StdDuration>) -> anyhow::Result<()> {
+    pub fn apply_timebox(
+        &mut self,
+        timebox: Option<StdDuration>,
+        from_start: bool,
+    ) -> anyhow::Result<()> {
         if let Some(job) = self.job_board.active_stack.last_mut() {
             job.timebox = timebox;
+
+            // Unless asked to measure from the job's start, refresh the
+            // job's begin date so that the timebox just applied is measured from now
+            if !from_start {
+                job.begin_date = Utc::now();
+            }
+
             match timebox {
                 Some(timebox) => {
                     let formatted_duration = humantime::format_duration(timebox);
@@ -381,15 +1597,19 @@ impl WydApplication {
                         t = formatted_duration,
                         j = job.label
                     );
+                    if let Some(expiry) = job.timebox_expiry() {
+                        let expiry = settings::in_timezone(expiry, self.settings.timezone);
+                        println!("Timebox expires {}", expiry.format("%a %F %r"));
+                    }
                 }
                 None => {
                     println!("Removed timebox from job \"{j}\"", j = job.label);
                 }
             }
 
-            // Refresh the job's begin date, so that the timebox
-            // just applied is measured from now
-            job.begin_date = Utc::now();
+            // Acting on the job resets its reminder backoff
+            job.last_notification = None;
+            job.notification_count = 0;
 
             self.save().context("Unable to save after applying timebox.")?;
         } else {
@@ -398,120 +1618,2559 @@ impl WydApplication {
         Ok(())
     }
 
+    pub fn estimate_current_job(&mut self, estimate: StdDuration) -> anyhow::Result<()> {
+        match self.job_board.active_stack.last_mut() {
+            Some(job) => {
+                job.estimate = Some(estimate);
+                println!(
+                    "Estimated job \"{j}\" at {e}",
+                    j = job.label,
+                    e = humantime::format_duration(estimate)
+                );
+                self.save().context("Unable to save after estimating job.")?;
+            }
+            None => println!("No active job to estimate."),
+        }
+        Ok(())
+    }
+
     pub fn print_current_timebox(&self) {
         if let Some(job) = self.job_board.active_stack.last() {
-            if let Some(timebox) = job.timebox {
-                let timebox = match chrono::Duration::from_std(timebox) {
-                    Ok(timebox) => timebox,
-                    Err(_) => todo!(),
-                };
-                let expiry_utc = match job.begin_date.checked_add_signed(timebox) {
-                    Some(expiry) => expiry,
-                    None => todo!(),
-                };
-                let expiry = chrono::DateTime::<Local>::from(expiry_utc);
+            if let Some(expiry_utc) = job.timebox_expiry() {
+                let expiry = self.local_time(expiry_utc);
                 println!("Current timebox: {}", expiry.format("%a %F %r"))
             }
         }
     }
 
+    pub fn print_all_timeboxes(&self) {
+        let mut expiries: Vec<_> = self
+            .job_board
+            .active_stack
+            .iter()
+            .filter_map(|job| job.timebox_expiry().map(|expiry| (expiry, job)))
+            .collect();
+        if expiries.is_empty() {
+            println!("No active jobs have a timebox.");
+            return;
+        }
+        expiries.sort_by_key(|(expiry, _job)| *expiry);
+        for (expiry_utc, job) in expiries {
+            let expiry = self.local_time(expiry_utc);
+            println!("{}:  {}", expiry.format("%a %F %r"), job.label);
+        }
+    }
+
+    /// Starts the clock of the topmost paused job, or of the paused job matching `pattern`.
+    pub fn start_job_named(&mut self, pattern: Option<&str>, first: bool) -> anyhow::Result<()> {
+        let paused: Vec<(usize, String)> = self
+            .job_board
+            .active_stack
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, job)| job.paused_since.is_some())
+            .filter(|(_, job)| match pattern {
+                Some(pattern) => match pattern.strip_prefix('#') {
+                    Some(id) => job.id == id,
+                    None => job.label.contains(pattern),
+                },
+                None => true,
+            })
+            .map(|(index, job)| (index, job.label.clone()))
+            .collect();
+        let index = match (pattern, paused.first()) {
+            (_, None) => {
+                println!("No paused job to start.");
+                return Ok(());
+            }
+            (None, Some((index, _label))) => *index,
+            (Some(_), Some(_)) => match self.choose_candidate(&paused, first) {
+                Some(index) => index,
+                None => return Ok(()),
+            },
+        };
+        let job = &mut self.job_board.active_stack[index];
+        job.unpause(Utc::now());
+        let message = format!("Started job \"{}\"", job.label);
+        self.print(&self.indent(message));
+        self.save().context("Unable to save after starting job.")
+    }
+
+    /// Cancels the top job, or the active job matching `pattern` along with
+    /// the subtasks above it.
+    pub fn cancel_job_named(
+        &mut self,
+        pattern: Option<&str>,
+        reason: Option<String>,
+        fuzzy: bool,
+        first: bool,
+    ) -> anyhow::Result<()> {
+        let count = match pattern {
+            Some(pattern) => {
+                let matches = if let Some(id) = pattern.strip_prefix('#') {
+                    self.job_board.find_job_by_id(id).into_iter().collect()
+                } else if fuzzy {
+                    self.job_board.matching_jobs(fuzzy_matcher(pattern))
+                } else {
+                    self.job_board.matching_jobs(substring_matcher(pattern))
+                };
+                if matches.is_empty() {
+                    println!("No matching job to cancel.");
+                    return Ok(());
+                }
+                let candidates: Vec<_> = matches
+                    .into_iter()
+                    .map(|i| (i, self.job_board.active_stack[i].label.clone()))
+                    .collect();
+                match self.choose_candidate(&candidates, first) {
+                    Some(index) => self.job_board.active_stack.len() - index,
+                    None => return Ok(()),
+                }
+            }
+            None => 1,
+        };
+        self.complete_current_jobs(DoneOptions {
+            cancelled: true,
+            count,
+            reason,
+            ..DoneOptions::default()
+        })
+    }
+
     pub fn suspend_job_named(
         &mut self,
         pattern: &str,
-        reason: String,
-        timer: Option<DateTime<Utc>>,
+        options: SuspendOptions,
+        fuzzy: bool,
+        first: bool,
     ) {
-        let matcher = substring_matcher(&pattern);
-        if self
-            .job_board
-            .suspend_matching(matcher, reason, timer)
-            .is_ok()
-        {
+        let index = match self.choose_active_job(pattern, fuzzy, first) {
+            Ok(Some(index)) => index,
+            Ok(None) => return,
+            Err(()) => {
+                println!("No matching job to suspend.");
+                return;
+            }
+        };
+        if self.job_board.suspend_at(index, options).is_ok() {
             println!("Job suspended.");
+        }
+    }
+
+    // Err if nothing matches, Ok(None) if the choice was abandoned.
+    fn choose_active_job(
+        &mut self,
+        pattern: &str,
+        fuzzy: bool,
+        first: bool,
+    ) -> Result<Option<usize>, ()> {
+        let matches = if let Some(id) = pattern.strip_prefix('#') {
+            self.job_board.find_job_by_id(id).into_iter().collect()
+        } else if fuzzy {
+            self.job_board.matching_jobs(fuzzy_matcher(pattern))
+        } else {
+            self.job_board.matching_jobs(substring_matcher(pattern))
+        };
+        if matches.is_empty() {
+            return Err(());
+        }
+        let candidates: Vec<_> = matches
+            .into_iter()
+            .map(|i| (i, self.job_board.active_stack[i].label.clone()))
+            .collect();
+        Ok(self.choose_candidate(&candidates, first))
+    }
+
+    /// Suspends the current job, or the one matching `pattern`, onto the board in
+    /// another data directory, e.g. to keep work and personal tasks apart.
+    /// That board is created if it doesn't exist yet.
+    pub fn suspend_job_to(
+        &mut self,
+        pattern: &str,
+        options: SuspendOptions,
+        fuzzy: bool,
+        first: bool,
+        target_dir: &Path,
+    ) -> anyhow::Result<()> {
+        let index = if pattern.is_empty() {
+            self.job_board.active_stack.len().checked_sub(1)
+        } else {
+            match self.choose_active_job(pattern, fuzzy, first) {
+                Ok(index) => index,
+                Err(()) => {
+                    println!("No matching job to suspend.");
+                    return Ok(());
+                }
+            }
+        };
+        let index = match index {
+            Some(index) => index,
+            None => {
+                if pattern.is_empty() {
+                    println!("No job to suspend.");
+                }
+                return Ok(());
+            }
+        };
+        let same_dir = match (fs::canonicalize(target_dir), fs::canonicalize(&self.app_dir)) {
+            (Ok(target_dir), Ok(app_dir)) => target_dir == app_dir,
+            _ => false,
+        };
+        if same_dir {
+            self.reject(format!("{:?} is already the data directory in use.", target_dir));
+            return Ok(());
+        }
+
+        let _target_lock = StateLock::acquire(target_dir)?;
+        let mut target = WydApplication::load(target_dir.to_path_buf())
+            .with_context(|| format!("Failed to load the board in {:?}", target_dir))?;
+        let jobs = self.job_board.active_stack.split_off(index);
+        let label = jobs[0].label.clone();
+        target.job_board.add_suspended_stack(options.into_stack(jobs));
+        // Saving the target first means a failure can only leave the job on
+        // both boards, never on neither
+        target.save().context("Unable to save the board the job was moved to.")?;
+        self.save().context("Unable to save after moving a job to another board.")?;
+        println!("Job \"{}\" suspended to {:?}", label, target_dir);
+        Ok(())
+    }
+
+    // Err if nothing matches, Ok(None) if the choice was abandoned.
+    fn choose_suspended_stack(
+        &mut self,
+        pattern: &str,
+        fuzzy: bool,
+        first: bool,
+    ) -> Result<Option<usize>, ()> {
+        let matches = if let Some(id) = pattern.strip_prefix('#') {
+            self.job_board
+                .find_suspended_stack_by_id(id)
+                .into_iter()
+                .collect()
+        } else if fuzzy {
+            self.job_board
+                .matching_suspended_stacks(fuzzy_matcher(pattern))
         } else {
-            println!("No matching job to suspend.")
+            self.job_board
+                .matching_suspended_stacks(substring_matcher(pattern))
+        };
+        if matches.is_empty() {
+            return Err(());
         }
+        let candidates: Vec<_> = matches
+            .into_iter()
+            .map(|i| (i, self.job_board.suspended_stacks[i].data[0].label.clone()))
+            .collect();
+        Ok(self.choose_candidate(&candidates, first))
+    }
+
+    // Offers every suspended stack in an interactive picker, using fzf if it's installed.
+    fn pick_suspended_stack(&mut self, query: &str) -> Option<usize> {
+        let entries: Vec<String> = self
+            .job_board
+            .suspended_stacks
+            .iter()
+            .map(|stack| match stack.reason.as_str() {
+                "None" | "" => format!("{} #{}", stack.data[0].label, stack.id()),
+                reason => format!("{} ({}) #{}", stack.data[0].label, reason, stack.id()),
+            })
+            .collect();
+        self.unlocked(|| match pick_with_fzf(&entries, query) {
+            Ok(choice) => choice,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                pick_with_prompt(&entries, query)
+            }
+            Err(error) => {
+                eprintln!("Unable to run fzf: {}", error);
+                pick_with_prompt(&entries, query)
+            }
+        })?
     }
 
-    pub fn resume_job_named(&mut self, pattern: &str) -> anyhow::Result<()> {
-        let outcome = if pattern.is_empty() {
-            self.job_board.resume_at_index(0)
+    pub fn resume_job_named(&mut self, pattern: &str, options: ResumeOptions) -> anyhow::Result<()> {
+        if options.stack_into_current {
+            match self.job_board.active_stack.last() {
+                None => {
+                    self.reject("There's no current job to resume the task into.");
+                    return Ok(());
+                }
+                Some(Job {
+                    timebox: Some(_),
+                    label,
+                    ..
+                }) => {
+                    // Timeboxed tasks cannot have subtasks
+                    self.reject(format!(
+                        "Job \"{}\" has a timebox, so it can't take subtasks. \
+                        Remove the timebox before resuming a task into it.",
+                        label
+                    ));
+                    return Ok(());
+                }
+                Some(_) => {}
+            }
+        }
+        let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+        let picked = if options.pick && interactive && !self.job_board.suspended_stacks.is_empty() {
+            self.pick_suspended_stack(pattern)
+        } else {
+            None
+        };
+        // Without a pick, fall back on the usual matching
+        let index = if let Some(index) = picked {
+            index
+        } else if options.newest || options.oldest {
+            match self.job_board.stack_by_suspension_date(options.newest) {
+                Some(index) => index,
+                None => {
+                    self.reject("No suspended jobs to resume.");
+                    return Ok(());
+                }
+            }
+        } else if pattern.is_empty() {
+            0
+        } else {
+            match self.choose_suspended_stack(pattern, options.fuzzy, options.first) {
+                Ok(Some(index)) => index,
+                Ok(None) => return Ok(()),
+                Err(()) => {
+                    self.reject("No matching job to resume.");
+                    return Ok(());
+                }
+            }
+        };
+
+        let time_suspended = match self.job_board.suspended_stacks.get(index) {
+            Some(stack) => stack.age(Utc::now()).to_std().unwrap_or_default(),
+            None => StdDuration::ZERO,
+        };
+        let resumed_from = self.job_board.active_stack.len();
+        let outcome = if options.keep_suspended {
+            self.job_board.resume_copy_at_index(index)
         } else {
-            self.job_board.resume_matching(substring_matcher(&pattern))
+            self.job_board.resume_at_index(index, options.keep_times)
         };
+        if options.timebox_remaining && outcome.is_ok() {
+            for job in &mut self.job_board.active_stack[resumed_from..] {
+                // A paused job's clock didn't run while it was suspended anyway
+                if job.paused_since.is_some() {
+                    continue;
+                }
+                if let Some(timebox) = &mut job.timebox {
+                    *timebox += time_suspended;
+                }
+            }
+        }
 
         if let Some(new_top) = outcome.ok().and(self.job_board.active_stack.last()) {
             println!("Job resumed: {}", new_top);
+            if options.keep_suspended {
+                println!("The original is still suspended.");
+            }
+            // The new top job has no subtasks, so it can always take a timebox
+            if let Some(timebox) = options.timebox {
+                return self.apply_timebox(Some(timebox), false);
+            }
         } else {
-            eprintln!("No matching job to resume.");
+            self.reject("No matching job to resume.");
         }
         self.save().context("Unable to save after resuming job")?;
         Ok(())
     }
 
-    pub fn complete_current_job(&mut self, cancelled: bool) -> anyhow::Result<()> {
-        match self.job_board.pop() {
-            Some(job) => {
-                let duration = Local::now().signed_duration_since(job.begin_date);
-                let non_negative_dur = chrono::Duration::seconds(duration.num_seconds())
-                    .to_std()
-                    .unwrap_or(std::time::Duration::new(0, 0));
-                let duration_str = humantime::format_duration(non_negative_dur);
-
-                let log_line = format!(
-                    "{indent}{verb} job \"{j}\" (time elapsed: {t})",
-                    indent = self.get_indent(),
-                    verb = if cancelled { "Cancelled" } else { "Finished" },
-                    j = job.label,
-                    t = duration_str
+    /// Resumes every ready suspended stack, leaving the most urgent on top.
+    /// With `dry_run`, only prints what would be resumed.
+    pub fn resume_all_ready(&mut self, keep_times: bool, dry_run: bool) -> anyhow::Result<()> {
+        let ready = self.job_board.ready_stack_indices(Utc::now());
+        if ready.is_empty() {
+            println!("No suspended jobs are ready to resume.");
+            return Ok(());
+        }
+        let resumed_jobs: usize = ready
+            .iter()
+            .map(|index| self.job_board.suspended_stacks[*index].data.len())
+            .sum();
+        let depth = self.job_board.active_stack.len() + resumed_jobs;
+        if dry_run {
+            println!(
+                "[dry-run] Would resume {} suspended {}, in this order:",
+                ready.len(),
+                if ready.len() == 1 { "task" } else { "tasks" }
+            );
+            for (number, index) in ready.iter().enumerate() {
+                let stack = &self.job_board.suspended_stacks[*index];
+                let subtasks = match stack.data.len() - 1 {
+                    0 => String::new(),
+                    1 => " (and 1 subtask)".to_owned(),
+                    count => format!(" (and {} subtasks)", count),
+                };
+                println!(
+                    "[dry-run] {:>3}. {}{} #{}",
+                    number + 1,
+                    stack.data[0].label,
+                    subtasks,
+                    stack.id()
                 );
-                self.print(&log_line);
-                if let Some(new_job) = self.job_board.active_stack.last() {
-                    println!("{}", new_job)
-                } else {
-                    print!("{}", self.job_board.get_summary())
-                }
-                self.save().context("Unable to save after completing job")?;
-                Ok(())
             }
-            None => {
-                print!("{}", self.job_board.empty_stack_message());
-                Ok(())
+            println!("[dry-run] The active stack would be {} jobs deep.", depth);
+            return Ok(());
+        }
+        for index in ready {
+            let label = self.job_board.suspended_stacks[index].data[0].label.clone();
+            if self.job_board.resume_at_index(index, keep_times).is_ok() {
+                println!("Resumed job \"{}\".", label);
             }
         }
+        println!("The active stack is now {} jobs deep.", depth);
+        self.save().context("Unable to save after resuming jobs")?;
+        Ok(())
     }
 
-    pub fn get_summary(&self) -> String {
-        self.job_board.get_summary()
-    }
-
-    #[allow(dead_code)]
-    pub fn write_html(&mut self) {
-        let output = self.job_board.generate_html();
-        match fs::write(self.app_dir.join("wyd-homepage.html"), output) {
-            Ok(()) => (),
-            Err(x) => self.append_to_log(&format!(
-                "Could not write to html summary due to this error: {}",
-                x
-            )),
+    pub fn is_current_job(&self, id: &str) -> bool {
+        let id = id.strip_prefix('#').unwrap_or(id);
+        match self.job_board.active_stack.last() {
+            Some(job) => job.id == id,
+            None => false,
         }
     }
 
-    pub fn print_log(&self) {
+    /// Finishes the top jobs of the active stack with a single save.
+    pub fn complete_current_jobs(&mut self, options: DoneOptions) -> anyhow::Result<()> {
+        let DoneOptions {
+            cancelled,
+            count,
+            show_time,
+            json,
+            time_tag,
+            reason,
+        } = options;
+        // JSON goes to stdout by itself, so everything else moves to stderr
+        let say = |message: &str| {
+            if json {
+                eprintln!("{}", message)
+            } else {
+                println!("{}", message)
+            }
+        };
+        if self.job_board.active_stack.is_empty() {
+            say(self.job_board.empty_stack_message().trim_end());
+            return Ok(());
+        }
+        let count = count.min(self.job_board.active_stack.len());
+        let mut released = Vec::new();
+        for _ in 0..count {
+            let mut job = match self.job_board.pop() {
+                Some(job) => job,
+                None => break,
+            };
+            // A job that never started took no time
+            job.unpause(Utc::now());
+            let duration = job.elapsed(Utc::now());
+            let non_negative_dur = chrono::Duration::seconds(duration.num_seconds())
+                .to_std()
+                .unwrap_or(std::time::Duration::new(0, 0));
+            // The history keeps the exact times, only what's shown is rounded
+            let non_negative_dur = stats::round_up(non_negative_dur, self.settings.time_rounding);
+            let duration_str = humantime::format_duration(non_negative_dur);
+
+            let estimate_str = match job.estimate {
+                Some(estimate) => format!(", estimate: {}", humantime::format_duration(estimate)),
+                None => String::new(),
+            };
+            let verb = if cancelled { "Cancelled" } else { "Finished" };
+            let reason_str = match &reason {
+                Some(reason) => format!(", reason: {}", reason),
+                None => String::new(),
+            };
+            let display_line = format!(
+                "{indent}{verb} job \"{j}\" (time elapsed: {t}{e}{r})",
+                indent = self.get_indent(),
+                verb = verb,
+                j = job.label,
+                t = duration_str,
+                e = estimate_str,
+                r = reason_str
+            );
+            let log_line = self.job_log_line(display_line.clone(), verb, &job, non_negative_dur);
+            self.append_to_log(&(log_line + "\n"));
+            say(display_line.trim());
+            if json {
+                let elapsed = serde_json::json!({
+                    "id": job.id,
+                    "label": job.label,
+                    "elapsed_secs": non_negative_dur.as_secs(),
+                });
+                println!("{}", elapsed);
+            } else if show_time {
+                println!("⏱ {}", duration_str);
+            }
+            self.record_completion(&job, cancelled, time_tag.clone(), reason.clone());
+            for unblocked in self.job_board.unblock_dependents(&job.id) {
+                say(&format!("Job \"{}\" is now unblocked.", unblocked));
+            }
+            released.extend(self.job_board.release_waiting_stacks(&job.id));
+        }
+        // Resume waiting stacks only after the count is done, so they can't be completed too
+        for id in released {
+            let index = match self.job_board.find_suspended_stack_by_id(&id) {
+                Some(index) => index,
+                None => continue,
+            };
+            let stack = &self.job_board.suspended_stacks[index];
+            let label = stack.data[0].label.clone();
+            if !stack.auto_resume {
+                say(&format!("Job \"{}\" is ready to resume.", label));
+            } else if self.job_board.resume_at_index(index, false).is_ok() {
+                say(&format!("Resumed job \"{}\".", label));
+            }
+        }
+        if count > 1 {
+            say(&format!(
+                "{} {} jobs.",
+                if cancelled { "Cancelled" } else { "Completed" },
+                count
+            ));
+        }
+        for message in self.celebrate_goals() {
+            self.append_to_log(&format!("{}\n", self.timestamp(&message)));
+            say(&message);
+        }
+        if let Some(new_job) = self.job_board.active_stack.last() {
+            say(&new_job.to_string())
+        } else {
+            say(self.job_board.get_summary().trim_end())
+        }
+        self.save().context("Unable to save after completing job")?;
+        Ok(())
+    }
+
+    /// Pushes a new job with the last finished job's label and tags,
+    /// and with `keep_timebox`, its timebox too.
+    pub fn repeat_last_completion(&mut self, keep_timebox: bool) -> anyhow::Result<()> {
+        let mut records: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        let record = match records.pop() {
+            Some(record) => record,
+            None => {
+                self.reject("No finished jobs to repeat yet. Use `wyd push` to start one.");
+                return Ok(());
+            }
+        };
+        let options = PushOptions {
+            timebox: if keep_timebox { record.timebox } else { None },
+            tags: record.tags,
+            ..Default::default()
+        };
+        self.create_job(record.label, options)
+    }
+
+    /// Puts the most recently finished job back on top of the active stack,
+    /// as if it had never been marked done.
+    pub fn undo_completion(&mut self) -> anyhow::Result<()> {
+        let path = self.completion_records_path();
+        let mut records: Vec<CompletionRecord> = history::read_records(&path)?;
+        let record = match records.pop() {
+            Some(record) => record,
+            None => {
+                self.reject("No finished jobs to undo.");
+                return Ok(());
+            }
+        };
+        let since_done = Utc::now().signed_duration_since(record.end_date);
+        if since_done > Duration::hours(UNDO_LIMIT_HOURS) {
+            self.reject(format!(
+                "\"{}\" was finished more than {} hours ago, so it can't be undone.",
+                record.label, UNDO_LIMIT_HOURS
+            ));
+            return Ok(());
+        }
+
+        // Shift the start forward so the time since it was finished doesn't count
+        let job = Job {
+            id: record.id,
+            timebox: record.timebox,
+            tags: record.tags,
+            estimate: record.estimate,
+            ..Job::new(record.label, record.begin_date + since_done.max(Duration::zero()))
+        };
+        history::write_records(&path, &records)?;
+        self.print(&self.indent(format!("Reopened job \"{}\"", job.label)));
+        self.job_board.active_stack.push(job);
+        self.save().context("Unable to save after undoing completion.")?;
+        Ok(())
+    }
+
+    pub fn get_summary(&self) -> String {
+        self.job_board.get_summary()
+    }
+
+    pub fn prompt_summary(&self, max_width: usize) -> String {
+        self.job_board.prompt_summary(max_width)
+    }
+
+    pub fn get_summary_since(&self, since: DateTime<Utc>) -> String {
+        self.job_board.get_summary_since(since)
+    }
+
+    pub fn get_tree_summary(&self) -> String {
+        self.job_board.get_tree_summary()
+    }
+
+    pub fn print_timebox_remaining_total(&self) {
+        match self.job_board.timebox_remaining_total() {
+            (_, 0) => println!("No active task has a timebox."),
+            (total, count) => println!(
+                "{} of timeboxed time left across {} {}.",
+                humantime::format_duration(StdDuration::from_secs(total.as_secs())),
+                count,
+                if count == 1 { "task" } else { "tasks" }
+            ),
+        }
+    }
+
+    pub fn get_wide_summary(&self) -> anyhow::Result<String> {
+        let notes: Vec<NoteRecord> = history::read_records(&self.notes_path())?;
+        let mut note_counts = HashMap::new();
+        for job_id in notes.into_iter().filter_map(|note| note.job_id) {
+            *note_counts.entry(job_id).or_insert(0) += 1;
+        }
+        Ok(self.job_board.get_wide_summary(&note_counts))
+    }
+
+    /// Redraws the board whenever jobs.ron changes, until interrupted.
+    pub fn watch(&mut self) -> anyhow::Result<()> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(sender).context("Unable to watch for file changes.")?;
+        // Saves replace jobs.ron instead of writing to it, so watch the whole directory
+        watcher
+            .watch(&self.app_dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Unable to watch {:?}", self.app_dir))?;
+        self.redraw_watched_board();
+        for event in &receiver {
+            let jobs_changed = match event {
+                Ok(event) => event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == Some(OsStr::new("jobs.ron"))),
+                Err(error) => {
+                    eprintln!("Error while watching for changes: {}", error);
+                    false
+                }
+            };
+            if !jobs_changed {
+                continue;
+            }
+            std::thread::sleep(StdDuration::from_millis(WATCH_DEBOUNCE_MILLIS));
+            while receiver.try_recv().is_ok() {}
+            self.reload_watched_board();
+        }
+        Ok(())
+    }
+
+    fn reload_watched_board(&mut self) {
+        let mut attempts = 0;
+        let mut board = loop {
+            match JobBoard::load_read_only(&self.app_dir) {
+                Ok(board) => break board,
+                // Probably caught partway through an external edit or sync
+                Err(_) if attempts < WATCH_RETRIES => {
+                    attempts += 1;
+                    std::thread::sleep(StdDuration::from_millis(WATCH_DEBOUNCE_MILLIS));
+                }
+                Err(error) => {
+                    eprintln!("{:#}\nWaiting for the next change...", error);
+                    return;
+                }
+            }
+        };
+        board.migrate();
+        self.job_board = board;
+        self.redraw_watched_board();
+    }
+
+    fn redraw_watched_board(&self) {
+        // Clears the screen, so the board stays in place on a second monitor
+        print!("\x1b[2J\x1b[H");
+        println!(
+            "Updated at {}\n",
+            self.local_time(Utc::now()).format("%r")
+        );
+        print!("{}", self.get_summary());
+        let problems = import::validate_board(&self.job_board);
+        if !problems.is_empty() {
+            println!("\nProblems with jobs.ron:");
+            for problem in problems {
+                println!("  - {}", problem);
+            }
+        }
+        io::stdout().flush().ok();
+        self.write_html();
+    }
+
+    pub fn write_html(&self) {
+        // Generating the page sorts the board, so work from a copy
+        let output = self.job_board.clone().generate_html();
+        match write_atomically(&self.app_dir.join("wyd-homepage.html"), output.as_bytes()) {
+            Ok(()) => (),
+            Err(x) => self.append_to_log(&format!(
+                "Could not write to html summary due to this error: {}",
+                x
+            )),
+        }
+    }
+
+    pub fn import(&mut self, path: &Path, skip_confirmation: bool) -> anyhow::Result<()> {
+        let mut board = import::read_board(path)?;
+        board.migrate();
+        board.forget_missing_dependencies();
+        let errors = import::validate_board(&board);
+        if !errors.is_empty() {
+            self.reject(format!("Refusing to import {:?}:", path));
+            for error in errors {
+                eprintln!("  - {}", error);
+            }
+            return Ok(());
+        }
+
+        if !skip_confirmation {
+            let question = format!(
+                "Replace the current {} active and {} suspended jobs?",
+                self.job_board.active_stack.len(),
+                self.job_board.suspended_stacks.len()
+            );
+            match self.unlocked(|| confirm(&question)) {
+                Some(true) => {}
+                Some(false) => {
+                    self.reject("Nothing imported. Use --yes to import without confirmation.");
+                    return Ok(());
+                }
+                None => return Ok(()),
+            }
+        }
+
+        // Saving backs up the board being replaced
+        self.job_board = board;
+        self.job_board.sort_suspended_stacks();
+        self.save().context("Unable to save imported jobs.")?;
+        println!(
+            "Imported {} active and {} suspended jobs.",
+            self.job_board.active_stack.len(),
+            self.job_board.suspended_stacks.len()
+        );
+        Ok(())
+    }
+
+    pub fn compact(&mut self, prune_history: Option<StdDuration>) -> anyhow::Result<()> {
+        let jobs_path = self.app_dir.join("jobs.ron");
+        let file_size = |path: &Path| fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0);
+
+        // Loading already ran any migrations, and saving backs up the old file first
+        let size_before = file_size(&jobs_path);
+        self.save().context("Unable to save compacted jobs.")?;
+        println!(
+            "Compacted jobs.ron from {} to {} bytes.",
+            size_before,
+            file_size(&jobs_path)
+        );
+
+        if let Some(max_age) = prune_history {
+            let cutoff = Utc::now() - Duration::from_std(max_age).context("Age out of range.")?;
+            let path = self.completion_records_path();
+            let mut records: Vec<CompletionRecord> = history::read_records(&path)?;
+            let count_before = records.len();
+            records.retain(|record| record.end_date >= cutoff);
+            if records.len() < count_before {
+                fs::copy(&path, path.with_extension("ron.bak"))
+                    .context("Unable to back up the completion history.")?;
+                history::write_records(&path, &records)?;
+            }
+            println!(
+                "Pruned {} finished jobs from the history.",
+                count_before - records.len()
+            );
+        }
+        Ok(())
+    }
+
+    pub fn open(&self, html: bool) {
+        let path = if html {
+            self.write_html();
+            self.app_dir.join("wyd-homepage.html")
+        } else {
+            self.app_dir.clone()
+        };
+        if let Err(error) = opener::open(&path) {
+            // Headless machines have nothing to open it with, so say where it is instead
+            eprintln!("Unable to open {:?}: {}", path, error);
+            println!("{}", path.display());
+        }
+    }
+
+    pub fn open_links(&self) {
+        let job = match self.job_board.active_stack.last() {
+            Some(job) => job,
+            None => {
+                self.reject("No active job to open links for.");
+                return;
+            }
+        };
+        if job.links.is_empty() {
+            self.reject(format!("Job \"{}\" has no links.", job.label));
+        }
+        for link in &job.links {
+            if let Err(error) = opener::open(link.as_str()) {
+                eprintln!("Unable to open {}: {}", link, error);
+                println!("{}", link);
+            }
+        }
+    }
+
+    /// Condenses a day's history into a few bullet points for a journal.
+    pub fn print_day_summary(&self, day: NaiveDate, format: ReportFormat) -> anyhow::Result<()> {
+        let (start, end) = match stats::day_bounds(day, self.settings.timezone) {
+            Some(bounds) => bounds,
+            None => bail!("Unable to determine the bounds of {}", day),
+        };
+        let on_day = |date: DateTime<Utc>| date >= start && date < end;
+        let records: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        let (cancelled, finished): (Vec<_>, Vec<_>) = records
+            .into_iter()
+            .filter(|record| on_day(record.end_date))
+            .partition(|record| record.cancelled);
+        let notes: Vec<NoteRecord> = history::read_records(&self.notes_path())?
+            .into_iter()
+            .filter(|note: &NoteRecord| on_day(note.date))
+            .collect();
+
+        let format_duration = |duration: Duration| {
+            let secs = duration.num_seconds().max(0) as u64;
+            humantime::format_duration(StdDuration::from_secs(secs)).to_string()
+        };
+        let mut bullets = Vec::new();
+        if !finished.is_empty() {
+            let spans = finished
+                .iter()
+                .map(|record| (record.begin_date, record.end_date))
+                .collect();
+            bullets.push(format!(
+                "Finished {} {}, totaling {}",
+                finished.len(),
+                if finished.len() == 1 { "task" } else { "tasks" },
+                format_duration(stats::covered_time(spans))
+            ));
+            let took = |record: &CompletionRecord| {
+                record.end_date.signed_duration_since(record.begin_date)
+            };
+            if let Some(longest) = finished.iter().max_by_key(|record| took(record)) {
+                bullets.push(format!(
+                    "Longest task: {} ({})",
+                    longest.label,
+                    format_duration(took(longest))
+                ));
+            }
+        }
+        if !cancelled.is_empty() {
+            let labels: Vec<&str> = cancelled.iter().map(|record| record.label.as_str()).collect();
+            bullets.push(format!("Cancelled: {}", labels.join(", ")));
+        }
+        // Tagged notes were marked as worth finding again, so they go first
+        let mut notes = notes;
+        notes.sort_by_key(|note| note.tag.is_none());
+        for note in notes.iter().take(MAX_SUMMARY_NOTES) {
+            match &note.tag {
+                Some(tag) => bullets.push(format!("Noted [{}]: {}", tag, note.text)),
+                None => bullets.push(format!("Noted: {}", note.text)),
+            }
+        }
+        if notes.len() > MAX_SUMMARY_NOTES {
+            bullets.push(format!(
+                "...and {} more notes",
+                notes.len() - MAX_SUMMARY_NOTES
+            ));
+        }
+
+        let title = day.format("%a %F");
+        if bullets.is_empty() {
+            println!("Nothing was finished or jotted down on {}.", title);
+            return Ok(());
+        }
+        match format {
+            ReportFormat::Text => println!("{}:", title),
+            ReportFormat::Markdown => println!("## {}\n", title),
+        }
+        for bullet in bullets {
+            match format {
+                ReportFormat::Text => println!("  • {}", bullet),
+                ReportFormat::Markdown => println!("- {}", bullet),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn print_log(&self) {
         let log_path = self.current_log_path();
         let log_content =
             fs::read_to_string(log_path).unwrap_or("[Today's log is empty]".to_owned());
         println!("{}", log_content);
     }
 
-    pub fn add_log_note(&self, content: String) -> () {
-        let formatted_content = self.indent(self.timestamp(content));
-        self.append_to_log(&(formatted_content + "\n"))
+    // Prints the lines of today's log written since the top job started.
+    // Lines without a timestamp of their own belong to the last one seen.
+    pub fn print_current_log(&self) {
+        let begin_date = match self.job_board.active_stack.last() {
+            Some(job) => self.local_time(job.begin_date),
+            None => return self.print_log(),
+        };
+        if begin_date.naive_local().date() != self.today() {
+            return self.print_log();
+        }
+        let begin_time = begin_date.time().with_nanosecond(0).unwrap_or(begin_date.time());
+        let log_content = fs::read_to_string(self.current_log_path()).unwrap_or_default();
+        let mut in_session = false;
+        for line in log_content.lines() {
+            if let Some(time) = log_line_time(line) {
+                in_session = time >= begin_time;
+            }
+            if in_session {
+                println!("{}", line);
+            }
+        }
+    }
+
+    // Streams today's log as it grows, moving on to the next day's log
+    // after midnight. Runs until the process is interrupted (e.g. Ctrl-C),
+    // which is safe since following the log never modifies any state.
+    pub fn follow_log(&self) -> anyhow::Result<()> {
+        let mut log_path = self.current_log_path();
+        let mut position = 0;
+        loop {
+            if let Ok(mut file) = File::open(&log_path) {
+                let length = file.metadata()?.len();
+                if length < position {
+                    // The log was truncated, so start over
+                    position = 0;
+                }
+                if length > position {
+                    file.seek(SeekFrom::Start(position))?;
+                    let mut new_text = Vec::new();
+                    file.read_to_end(&mut new_text)?;
+                    position += new_text.len() as u64;
+                    let mut stdout = io::stdout();
+                    stdout.write_all(&new_text)?;
+                    stdout.flush()?;
+                }
+            }
+
+            let todays_log_path = self.current_log_path();
+            if todays_log_path != log_path {
+                log_path = todays_log_path;
+                position = 0;
+            } else {
+                std::thread::sleep(StdDuration::from_millis(500));
+            }
+        }
+    }
+
+    pub fn clear_log(&mut self, skip_confirmation: bool) -> anyhow::Result<()> {
+        let log_path = self.current_log_path();
+        let log_content = match fs::read_to_string(&log_path) {
+            Ok(content) => content,
+            Err(_) => {
+                println!("[Today's log is empty]");
+                return Ok(());
+            }
+        };
+        let line_count = log_content.lines().count();
+
+        if !skip_confirmation {
+            let question = format!("Clear {} lines from today's log?", line_count);
+            match self.unlocked(|| confirm(&question)) {
+                Some(true) => {}
+                Some(false) => {
+                    self.reject("Log not cleared. Use --yes to clear it without confirmation.");
+                    return Ok(());
+                }
+                None => return Ok(()),
+            }
+        }
+
+        let mut backup_path = log_path.as_os_str().to_owned();
+        backup_path.push(".bak");
+        fs::copy(&log_path, &backup_path).context("Unable to back up today's log.")?;
+        File::create(&log_path).context("Unable to clear today's log.")?;
+        println!(
+            "Cleared {} lines from today's log (saved to {:?}).",
+            line_count, backup_path
+        );
+        Ok(())
+    }
+
+    pub fn add_log_note(&self, content: String, tag: Option<String>) -> () {
+        let logged_content = match &tag {
+            Some(tag) => format!("[{}] {}", tag, content),
+            None => content.clone(),
+        };
+        let formatted_content = self.indent(self.timestamp(logged_content));
+        self.append_to_log(&(formatted_content + "\n"));
+
+        // Notes are also kept in one place, so they can be searched across days
+        let record = NoteRecord {
+            date: Utc::now(),
+            tag,
+            text: content,
+            job_id: self.job_board.active_stack.last().map(|job| job.id.clone()),
+        };
+        if let Err(error) = history::append_record(&self.notes_path(), &record) {
+            self.append_to_log(&format!("{:#}\n", error));
+        }
+    }
+
+    /// Reopens the last note jotted in an editor, and saves the changes to the notes
+    /// store. Its line in the day's log is rewritten when it can be found exactly,
+    /// or else a correction is added to today's log.
+    pub fn edit_last_note(&mut self) -> anyhow::Result<()> {
+        let path = self.notes_path();
+        let original = match history::read_records::<NoteRecord>(&path)?.pop() {
+            Some(note) => note,
+            None => {
+                self.reject("There are no notes to edit.");
+                return Ok(());
+            }
+        };
+        let instructions = "Edit the note above, which is saved as a single line.\n\
+            Leaving it empty keeps the note as it was.";
+        let edit_path = self.app_dir.join("JOT_EDITMSG");
+        let text = match self.unlocked(|| compose_in_editor(&edit_path, &original.text, instructions)) {
+            Some(text) => text?,
+            None => return Ok(()),
+        };
+        let text = match text {
+            Some(text) => text,
+            None => {
+                self.reject("Editing a note needs a terminal, and an editor in $VISUAL or $EDITOR.");
+                return Ok(());
+            }
+        };
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() || text == original.text {
+            println!("The note was left as it was.");
+            return Ok(());
+        }
+        self.replace_note(&original, text)
+    }
+
+    // Swaps the text of a note in the notes store, and in the day's log if it can
+    // be found there unambiguously. Otherwise the log gets a correction line.
+    fn replace_note(&mut self, original: &NoteRecord, text: String) -> anyhow::Result<()> {
+        let path = self.notes_path();
+        // More notes may have been jotted while the editor was open, so find it again
+        let mut notes: Vec<NoteRecord> = history::read_records(&path)?;
+        let note = notes
+            .iter_mut()
+            .rev()
+            .find(|note| note.date == original.date && note.text == original.text);
+        let note = match note {
+            Some(note) => note,
+            None => {
+                self.reject("The note was changed while it was being edited, so the edit wasn't saved.");
+                return Ok(());
+            }
+        };
+
+        let logged = |text: &str| match &note.tag {
+            Some(tag) => format!("[{}] {}", tag, text),
+            None => text.to_owned(),
+        };
+        let (old_logged, new_logged) = (logged(&note.text), logged(&text));
+        let note_day = self.local_time(note.date).naive_local().date();
+        let old_line = format!("{}: {}", self.local_time(note.date).format("%r"), old_logged);
+        note.text = text;
+        history::write_records(&path, &notes)?;
+
+        let log_path = self.log_path(note_day);
+        let log = fs::read_to_string(&log_path).unwrap_or_default();
+        let matching: Vec<usize> = log
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.trim_start() == old_line)
+            .map(|(number, _)| number)
+            .collect();
+        // Anything but exactly one match could rewrite the wrong line
+        if let [line_number] = matching[..] {
+            let lines: Vec<String> = log
+                .lines()
+                .enumerate()
+                .map(|(number, line)| {
+                    if number == line_number {
+                        line[..line.len() - old_logged.len()].to_owned() + &new_logged
+                    } else {
+                        line.to_owned()
+                    }
+                })
+                .collect();
+            write_atomically(&log_path, (lines.join("\n") + "\n").as_bytes())
+                .with_context(|| format!("Failed to rewrite the log at {:?}", log_path))?;
+        } else {
+            let correction = format!("(corrected: {})", new_logged);
+            self.append_to_log(&(self.indent(self.timestamp(correction)) + "\n"));
+        }
+        println!("Note changed to: {}", new_logged);
+        Ok(())
+    }
+
+    pub fn search_notes(&self, tag: Option<&str>, pattern: &str) -> anyhow::Result<()> {
+        let notes = self.matching_notes(tag, pattern)?;
+        if notes.is_empty() {
+            println!("No matching notes.");
+        }
+        for note in notes {
+            let date = self.local_time(note.date).format("%a %F %r");
+            match &note.tag {
+                Some(tag) => println!("{}: [{}] {}", date, tag, note.text),
+                None => println!("{}: {}", date, note.text),
+            }
+        }
+        Ok(())
+    }
+
+    // Notes containing `pattern`, and only those with `tag` when one is given
+    fn matching_notes(&self, tag: Option<&str>, pattern: &str) -> anyhow::Result<Vec<NoteRecord>> {
+        let mut notes: Vec<NoteRecord> = history::read_records(&self.notes_path())?;
+        notes.retain(|note| {
+            (tag.is_none() || note.tag.as_deref() == tag) && note.text.contains(pattern)
+        });
+        Ok(notes)
+    }
+
+    // Whether `now` falls in the configured work hours, which is always when none are set
+    fn in_work_hours(&self, now: DateTime<Utc>) -> bool {
+        match self.settings.work_hours {
+            Some(work_hours) => work_hours.contains(self.local_time(now).naive_local()),
+            None => true,
+        }
     }
 
     pub fn set_work_state(&mut self, work_state: WorkState) -> anyhow::Result<()> {
+        self.job_board.work_forced = work_state != WorkState::Off && !self.in_work_hours(Utc::now());
+        self.job_board.off_hours = false;
+        self.record_focus_change(self.job_board.work_state, work_state);
         self.job_board.work_state = work_state;
         self.save().context("Unable to save after setting work state.")?;
         Ok(())
     }
+
+    /// Holds off slack alarms for `duration`, after which the notifier switches
+    /// work mode back on by itself.
+    pub fn take_break(&mut self, duration: StdDuration) -> anyhow::Result<()> {
+        if self.job_board.work_state == WorkState::Off {
+            self.reject("Work mode is off, so there's no need for a break.");
+            return Ok(());
+        }
+        let break_end = Duration::from_std(duration)
+            .ok()
+            .and_then(|duration| Utc::now().checked_add_signed(duration))
+            .context("That break is too long.")?;
+        let break_state = WorkState::BreakUntil(break_end);
+        self.record_focus_change(self.job_board.work_state, break_state);
+        self.job_board.work_state = break_state;
+        self.save().context("Unable to save after starting a break.")?;
+        println!(
+            "On a break until {}. Run `wyd work` to end it early.",
+            self.local_time(break_end).format("%r")
+        );
+        Ok(())
+    }
+
+    pub fn print_work_status(&self) {
+        println!("{}", self.describe_work_state(Utc::now()));
+    }
+
+    fn describe_work_state(&self, now: DateTime<Utc>) -> String {
+        let format = |duration: Duration| {
+            let secs = duration.num_seconds().max(0) as u64;
+            humantime::format_duration(StdDuration::from_secs(secs)).to_string()
+        };
+        let work_state = self.job_board.work_state;
+        if work_state != WorkState::Off && !self.job_board.work_forced && !self.in_work_hours(now) {
+            return "Work mode is on, but held off until work hours.".to_owned();
+        }
+        match work_state {
+            WorkState::Off => "Work mode is off.".to_owned(),
+            WorkState::Working => "Work mode is on, and you're focused.".to_owned(),
+            WorkState::SlackingSince(since) => format!(
+                "Work mode is on, but you've been slacking for {}.",
+                format(now.signed_duration_since(since))
+            ),
+            // The notifier ends breaks, so one can outlast it when it isn't running
+            WorkState::BreakUntil(break_end) if break_end <= now => format!(
+                "Work mode is on, and your break ended at {}.",
+                self.local_time(break_end).format("%r")
+            ),
+            WorkState::BreakUntil(break_end) => format!(
+                "Work mode is on, but you're on a break until {} ({} left).",
+                self.local_time(break_end).format("%r"),
+                format(break_end.signed_duration_since(now))
+            ),
+        }
+    }
+
+    pub fn export(
+        &self,
+        format: ExportFormat,
+        output: Option<PathBuf>,
+        include_board: bool,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        anonymize: bool,
+    ) -> anyhow::Result<()> {
+        let mut records: Vec<CompletionRecord> = match format {
+            // The calendar only shows timers, so there's no need to read the history
+            ExportFormat::Ical => Vec::new(),
+            _ => history::read_records(&self.completion_records_path())?,
+        };
+        let anonymized_board;
+        let board = if anonymize {
+            let mut board = self.job_board.clone();
+            let mut anonymizer = Anonymizer::default();
+            anonymizer.board(&mut board);
+            anonymizer.records(&mut records);
+            anonymized_board = board;
+            &anonymized_board
+        } else {
+            &self.job_board
+        };
+        // A calendar of timers is the board, so it always includes it
+        let include_board = include_board || format == ExportFormat::Ical;
+        let data = ExportData::new(&records, board, include_board, since, until);
+        let exported = export::export_data(&data, format)?;
+        match output {
+            Some(path) => {
+                fs::write(&path, exported)
+                    .with_context(|| format!("Failed to write export to {:?}", path))?;
+                println!("Exported to {:?}", path);
+            }
+            None => print!("{}", exported),
+        }
+        Ok(())
+    }
+
+    pub fn export_history(
+        &self,
+        format: HistoryFormat,
+        output: Option<PathBuf>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<()> {
+        let mut records: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        records.retain(|record| export::in_range(record.end_date, since, until));
+        let exported = export::export_history(&records, format)?;
+        match output {
+            Some(path) => {
+                fs::write(&path, exported)
+                    .with_context(|| format!("Failed to write export to {:?}", path))?;
+                println!("Exported {} finished jobs to {:?}", records.len(), path);
+            }
+            None => print!("{}", exported),
+        }
+        Ok(())
+    }
+
+    pub fn print_settings(&self) -> anyhow::Result<()> {
+        let text = ser::to_string_pretty(&self.settings, PrettyConfig::new())
+            .context("Failed to serialize settings.")?;
+        println!("{}", text);
+        Ok(())
+    }
+
+    pub fn print_setting(&self, key: &str) -> anyhow::Result<()> {
+        match self.settings.get(key) {
+            Ok(value) => println!("{}", value),
+            Err(error) => self.reject(format!("{:#}", error)),
+        }
+        Ok(())
+    }
+
+    pub fn change_setting(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        if let Err(error) = self.settings.set(key, value) {
+            self.reject(format!("{:#}", error));
+            return Ok(());
+        }
+        self.settings
+            .save(&self.app_dir)
+            .context("Unable to save after changing setting.")?;
+        println!("{} = {}", key, self.settings.get(key)?);
+        Ok(())
+    }
+
+    /// Moves exactly the job with the given id: an active job is suspended
+    /// along with its subtasks, and a suspended stack is resumed.
+    pub fn move_job(
+        &mut self,
+        id: &str,
+        target: MoveTarget,
+        options: SuspendOptions,
+    ) -> anyhow::Result<()> {
+        let id = id.strip_prefix('#').unwrap_or(id);
+        let active_index = self.job_board.find_job_by_id(id);
+        let stack_index = self.job_board.find_suspended_stack_by_id(id);
+        match (target, active_index, stack_index) {
+            (MoveTarget::Suspended, Some(index), _) => {
+                let label = self.job_board.active_stack[index].label.clone();
+                let count = self.job_board.active_stack.len() - index;
+                if self.job_board.suspend_at(index, options).is_err() {
+                    self.reject(format!("Unable to suspend job #{}.", id));
+                    return Ok(());
+                }
+                match count {
+                    1 => println!("Suspended job \"{}\".", label),
+                    _ => println!("Suspended job \"{}\" and {} subtasks.", label, count - 1),
+                }
+            }
+            (MoveTarget::Active, _, Some(index)) => {
+                if self.job_board.resume_at_index(index, false).is_err() {
+                    self.reject(format!("Unable to resume job #{}.", id));
+                    return Ok(());
+                }
+                if let Some(new_top) = self.job_board.active_stack.last() {
+                    println!("Job resumed: {}", new_top);
+                }
+            }
+            (MoveTarget::Suspended, None, Some(_)) => {
+                self.reject(format!("Job #{} is already suspended.", id));
+                return Ok(());
+            }
+            (MoveTarget::Active, Some(_), None) => {
+                self.reject(format!("Job #{} is already active.", id));
+                return Ok(());
+            }
+            _ => {
+                let subtask_of = self
+                    .job_board
+                    .suspended_stacks
+                    .iter()
+                    .find(|stack| stack.data.iter().any(|job| job.id == id));
+                match subtask_of {
+                    Some(stack) => self.reject(format!(
+                        "Job #{} is a subtask in suspended stack #{}. Move the stack by that id instead.",
+                        id,
+                        stack.id()
+                    )),
+                    None => self.reject(format!("No job has the id #{}.", id)),
+                }
+                return Ok(());
+            }
+        }
+        self.save().context("Unable to save after moving job.")?;
+        Ok(())
+    }
+
+    pub fn snooze_job_named(&mut self, pattern: &str, timer: DateTime<Utc>) -> anyhow::Result<()> {
+        let index = match self.choose_suspended_stack(pattern, false, false) {
+            Ok(Some(index)) => index,
+            Ok(None) => return Ok(()),
+            Err(()) => {
+                self.reject("No matching suspended job to snooze.");
+                return Ok(());
+            }
+        };
+        let stack = &mut self.job_board.suspended_stacks[index];
+        stack.timer = Some(timer);
+        stack.last_notification = None;
+        stack.notification_count = 0;
+        println!(
+            "Snoozed job \"{}\" until {}",
+            stack.data[0].label,
+            settings::in_timezone(timer, self.settings.timezone).format("%a %F %r")
+        );
+        self.job_board.sort_suspended_stacks();
+        self.save().context("Unable to save after snoozing job.")?;
+        Ok(())
+    }
+
+    pub fn edit_suspension_reason(
+        &mut self,
+        pattern: &str,
+        reason: String,
+        fuzzy: bool,
+        first: bool,
+    ) -> anyhow::Result<()> {
+        let index = match self.choose_suspended_stack(pattern, fuzzy, first) {
+            Ok(Some(index)) => index,
+            Ok(None) => return Ok(()),
+            Err(()) => {
+                self.reject("No matching suspended job to edit.");
+                return Ok(());
+            }
+        };
+        let stack = &mut self.job_board.suspended_stacks[index];
+        let old_reason = std::mem::replace(&mut stack.reason, reason);
+        let message = format!(
+            "Changed the reason job \"{}\" is suspended from \"{}\" to \"{}\"",
+            stack.data[0].label, old_reason, stack.reason
+        );
+        self.print(&self.timestamp(message));
+        self.save().context("Unable to save after editing suspension reason.")?;
+        Ok(())
+    }
+
+    pub fn set_pinned(&mut self, pattern: &str, pinned: bool) -> anyhow::Result<()> {
+        let index = match self.choose_suspended_stack(pattern, false, false) {
+            Ok(Some(index)) => index,
+            Ok(None) => return Ok(()),
+            Err(()) => {
+                self.reject("No matching suspended job.");
+                return Ok(());
+            }
+        };
+        let stack = &mut self.job_board.suspended_stacks[index];
+        stack.pinned = pinned;
+        println!(
+            "{} job \"{}\"",
+            if pinned { "Pinned" } else { "Unpinned" },
+            stack.data[0].label
+        );
+        self.job_board.sort_suspended_stacks();
+        self.save().context("Unable to save after pinning job.")?;
+        Ok(())
+    }
+
+    pub fn context(&self) -> Option<&str> {
+        self.job_board.context.as_deref()
+    }
+
+    pub fn set_context(&mut self, context: Option<String>) -> anyhow::Result<()> {
+        match &context {
+            Some(context) => println!("New tasks will be tagged @{}", context),
+            None => println!("Cleared the context."),
+        }
+        self.job_board.context = context;
+        self.save().context("Unable to save after setting context.")?;
+        Ok(())
+    }
+
+    pub fn rename_tag(&mut self, old: &str, new: &str) -> anyhow::Result<()> {
+        let (updated, context_renamed) = self.job_board.rename_tag(old, new);
+        if updated == 0 && !context_renamed {
+            self.reject(format!("No tasks are tagged @{}", old));
+            return Ok(());
+        }
+        self.save().context("Unable to save after renaming tag.")?;
+        println!(
+            "Renamed @{} to @{} on {} task{}{}",
+            old,
+            new,
+            updated,
+            if updated == 1 { "" } else { "s" },
+            if context_renamed { " and the context" } else { "" }
+        );
+        Ok(())
+    }
+
+    pub fn print_focus_stats(&self, day: NaiveDate) -> anyhow::Result<()> {
+        let mut records: Vec<FocusRecord> = history::read_records(&self.focus_records_path())?;
+        records.sort_by_key(|record| record.date);
+
+        let (start, end) = match stats::day_bounds(day, self.settings.timezone) {
+            Some((start, end)) => (start, end.min(Utc::now())),
+            None => bail!("Unable to determine the bounds of {}", day),
+        };
+        let (focused, slacking) = stats::focus_totals(&records, start, end);
+
+        let format = |duration: Duration| {
+            let secs = duration.num_seconds().max(0) as u64;
+            humantime::format_duration(StdDuration::from_secs(secs)).to_string()
+        };
+        let total_secs = (focused + slacking).num_seconds();
+        let focused_percent = if total_secs > 0 {
+            focused.num_seconds() * 100 / total_secs
+        } else {
+            0
+        };
+        println!("Work mode for {}:", day.format("%a %F"));
+        println!("    Focused:  {} ({}%)", format(focused), focused_percent);
+        println!("    Slacking: {}", format(slacking));
+        Ok(())
+    }
+
+    /// A short "so far today" summary of the board and today's history.
+    pub fn print_now_stats(&self) -> anyhow::Result<()> {
+        let now = Utc::now();
+        let today = self.today();
+        let (start, _end) = match stats::day_bounds(today, self.settings.timezone) {
+            Some(bounds) => bounds,
+            None => bail!("Unable to determine the bounds of {}", today),
+        };
+        let format = |duration: Duration| {
+            let secs = duration.num_seconds().max(0) as u64;
+            humantime::format_duration(StdDuration::from_secs(secs)).to_string()
+        };
+
+        let completions: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        let finished_today = completions
+            .iter()
+            .filter(|record| !record.cancelled && record.end_date >= start);
+        let tasks_done = finished_today.count();
+        let mut focus_records: Vec<FocusRecord> =
+            history::read_records(&self.focus_records_path())?;
+        focus_records.sort_by_key(|record| record.date);
+        let (focused, _slacking) = stats::focus_totals(&focus_records, start, now);
+
+        println!(
+            "So far today you've finished {} {} and focused for {}.",
+            tasks_done,
+            if tasks_done == 1 { "task" } else { "tasks" },
+            format(focused)
+        );
+        match self.job_board.active_stack.last() {
+            Some(job) => println!(
+                "You're on \"{}\", started {} ago.",
+                job.label,
+                format(job.elapsed(now))
+            ),
+            None => println!("Nothing is in progress."),
+        }
+        println!("{}", self.describe_work_state(now));
+        if let Some(goal_summary) = self.goal_summary()? {
+            println!("{}", goal_summary);
+        }
+        Ok(())
+    }
+
+    pub fn print_tag_stats(&self, day: NaiveDate) -> anyhow::Result<()> {
+        let records: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        let (start, end) = match stats::day_bounds(day, self.settings.timezone) {
+            Some(bounds) => bounds,
+            None => bail!("Unable to determine the bounds of {}", day),
+        };
+        let totals = stats::time_by_tag(&records, start, end, self.settings.time_rounding);
+        if totals.is_empty() {
+            println!("No tagged tasks were finished on {}.", day.format("%a %F"));
+            return Ok(());
+        }
+        println!("Time by tag for {}:", day.format("%a %F"));
+        for (tag, total) in totals {
+            let secs = total.num_seconds().max(0) as u64;
+            println!(
+                "    @{}: {}",
+                tag,
+                humantime::format_duration(StdDuration::from_secs(secs))
+            );
+        }
+        Ok(())
+    }
+
+    pub fn print_heatmap(&self, weeks: u32) -> anyhow::Result<()> {
+        let records: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        let mut counts = HashMap::new();
+        for record in records.iter().filter(|record| !record.cancelled) {
+            let day = self.local_time(record.end_date).naive_local().date();
+            *counts.entry(day).or_insert(0) += 1;
+        }
+        print!(
+            "{}",
+            stats::heatmap(&counts, self.today(), weeks)
+        );
+        Ok(())
+    }
+
+    /// Charts when finished tasks were finished, per hour of the day or else
+    /// per day of the week, over the last `days` days or the whole history.
+    pub fn print_distribution(&self, per_hour: bool, days: Option<u32>) -> anyhow::Result<()> {
+        let mut records: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        if let Some(days) = days {
+            let since = Utc::now() - Duration::days(days as i64);
+            records.retain(|record| record.end_date >= since);
+        }
+        if records.iter().all(|record| record.cancelled) {
+            match days {
+                Some(days) => println!("No tasks were finished in the last {} days.", days),
+                None => println!("No tasks have been finished yet."),
+            }
+            return Ok(());
+        }
+        let bars: Vec<(String, u32)> = if per_hour {
+            stats::per_hour(&records, self.settings.timezone)
+                .iter()
+                .enumerate()
+                .map(|(hour, count)| (format!("{:02}:00", hour), *count))
+                .collect()
+        } else {
+            let weekdays = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+            weekdays
+                .iter()
+                .zip(stats::per_weekday(&records, self.settings.timezone).iter())
+                .map(|(weekday, count)| (weekday.to_string(), *count))
+                .collect()
+        };
+        print!("{}", stats::bar_chart(&bars));
+        Ok(())
+    }
+
+    pub fn print_streak(&self) -> anyhow::Result<()> {
+        let records: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        let days = records
+            .iter()
+            .filter(|record| !record.cancelled)
+            .map(|record| self.local_time(record.end_date).naive_local().date())
+            .collect();
+        let (current, longest) = stats::streaks(&days, self.today());
+        let plural = |count: u32| if count == 1 { "day" } else { "days" };
+        println!("Current streak: {} {}", current, plural(current));
+        println!("Longest streak: {} {}", longest, plural(longest));
+        Ok(())
+    }
+
+    pub fn print_estimate_accuracy(&self, format: ReportFormat) -> anyhow::Result<()> {
+        let records: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        let overall = match stats::estimate_accuracy(&records) {
+            Some(overall) => overall,
+            None => {
+                println!("No finished tasks have estimates yet. Add one with `wyd estimate`.");
+                return Ok(());
+            }
+        };
+        let mut tags = BTreeSet::new();
+        for record in &records {
+            tags.extend(record.tags.iter().cloned());
+        }
+
+        let row = |name: String, accuracy: stats::EstimateAccuracy| {
+            let bias = (accuracy.mean_bias * 100.0).round();
+            let tendency = if bias > 0.0 {
+                format!("underestimates by {}%", bias)
+            } else if bias < 0.0 {
+                format!("overestimates by {}%", -bias)
+            } else {
+                "on target".to_owned()
+            };
+            vec![
+                name,
+                accuracy.count.to_string(),
+                format!("{:.0}%", accuracy.mean_error * 100.0),
+                tendency,
+            ]
+        };
+        let mut rows = vec![row("(all)".to_owned(), overall)];
+        for tag in tags {
+            let tagged = records.iter().filter(|record| record.tags.contains(&tag));
+            if let Some(accuracy) = stats::estimate_accuracy(tagged) {
+                rows.push(row(format!("@{}", tag), accuracy));
+            }
+        }
+        print!(
+            "{}",
+            stats::table(&["Tag", "Tasks", "Mean error", "Tendency"], &rows, format)
+        );
+        Ok(())
+    }
+
+    pub fn write_trace(
+        &self,
+        since: Option<DateTime<Utc>>,
+        output: Option<PathBuf>,
+    ) -> anyhow::Result<()> {
+        let mut records: Vec<CompletionRecord> =
+            history::read_records(&self.completion_records_path())?;
+        if let Some(since) = since {
+            records.retain(|record| record.end_date >= since);
+        }
+        let trace = replay::chrome_trace(&records, &self.job_board.active_stack, Utc::now())?;
+        match output {
+            Some(path) => {
+                fs::write(&path, trace)
+                    .with_context(|| format!("Failed to write trace to {:?}", path))?;
+                println!("Wrote trace to {:?}. Load it from chrome://tracing to view it.", path);
+            }
+            None => println!("{}", trace),
+        }
+        Ok(())
+    }
+
+    pub fn replay(&self, day: NaiveDate, html: bool, output: Option<PathBuf>) -> anyhow::Result<()> {
+        let (start, end) = match stats::day_bounds(day, self.settings.timezone) {
+            Some(bounds) => bounds,
+            None => bail!("Unable to determine the bounds of {}", day),
+        };
+        let records = history::read_records(&self.completion_records_path())?;
+        let records = replay::records_between(records, start, end);
+
+        if !html {
+            if records.is_empty() {
+                println!("No tasks were finished on {}.", day.format("%a %F"));
+            } else {
+                print!("{}", replay::text_timeline(&records));
+            }
+            return Ok(());
+        }
+
+        let path = output.unwrap_or_else(|| {
+            self.app_dir
+                .join(format!("{}", day.format("wyd-replay-%F.html")))
+        });
+        fs::write(&path, replay::html_timeline(&records, day))
+            .with_context(|| format!("Failed to write timeline to {:?}", path))?;
+        println!("{}", path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // An app with its own data directory, which is removed when the `TempDir` drops
+    fn temp_app() -> (tempfile::TempDir, WydApplication) {
+        let app_dir = tempfile::tempdir().unwrap();
+        let app = WydApplication::load(app_dir.path().to_owned()).unwrap();
+        (app_dir, app)
+    }
+
+    // An app that works Mon-Fri 9-17 UTC
+    fn scheduled_app() -> (tempfile::TempDir, WydApplication) {
+        let (app_dir, mut app) = temp_app();
+        app.settings.timezone = Some(chrono_tz::UTC);
+        app.settings.work_hours = Some("Mon-Fri 9-17".parse().unwrap());
+        (app_dir, app)
+    }
+
+    // 2021-06-04 is a Friday
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.ymd(2021, 6, day).and_hms(hour, minute, 0)
+    }
+
+    // Pushes a job whose timebox of `minutes` started at `begin_date`
+    fn push_timeboxed(
+        app: &mut WydApplication,
+        label: &str,
+        minutes: u64,
+        begin_date: DateTime<Utc>,
+    ) {
+        let options = PushOptions {
+            timebox: Some(StdDuration::from_secs(minutes * 60)),
+            ..PushOptions::default()
+        };
+        app.create_job(label.to_owned(), options).unwrap();
+        app.job_board.active_stack.last_mut().unwrap().begin_date = begin_date;
+    }
+
+    // When reminders were sent, checking every 5 seconds for `minutes` from `from`
+    fn reminder_times(
+        app: &mut WydApplication,
+        from: DateTime<Utc>,
+        minutes: i64,
+    ) -> Vec<DateTime<Utc>> {
+        (0..minutes * 60 / 5)
+            .map(|step| from + Duration::seconds(step * 5))
+            .filter(|now| app.update_timers_at(*now).unwrap().send_alarm)
+            .collect()
+    }
+
+    // The seconds between each reminder and the next
+    fn gaps(times: &[DateTime<Utc>]) -> Vec<i64> {
+        times
+            .windows(2)
+            .map(|pair| pair[1].signed_duration_since(pair[0]).num_seconds())
+            .collect()
+    }
+
+    // Pushes a job and suspends it straight away, with a timer `minutes` from now
+    fn push_suspended(app: &mut WydApplication, label: &str, minutes: i64) {
+        app.create_job(label.to_owned(), PushOptions::default())
+            .unwrap();
+        let options = SuspendOptions {
+            timer: Some(Utc::now() + Duration::minutes(minutes)),
+            ..SuspendOptions::default()
+        };
+        app.job_board.suspend_current(options).unwrap();
+    }
+
+    fn suspended_labels(board: &JobBoard) -> Vec<&str> {
+        board
+            .suspended_stacks
+            .iter()
+            .map(|stack| stack.data[0].label.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn pins_survive_saving_and_sorting() {
+        let (app_dir, mut app) = temp_app();
+        push_suspended(&mut app, "soon", 5);
+        push_suspended(&mut app, "check CI", 60);
+        app.set_pinned("check CI", true).unwrap();
+
+        let mut board = JobBoard::load(app_dir.path());
+        assert_eq!(suspended_labels(&board), ["check CI", "soon"]);
+        assert!(board.suspended_stacks[0].pinned);
+        board.sort_suspended_stacks();
+        assert_eq!(suspended_labels(&board), ["check CI", "soon"]);
+
+        app.set_pinned("check CI", false).unwrap();
+        let board = JobBoard::load(app_dir.path());
+        assert_eq!(suspended_labels(&board), ["soon", "check CI"]);
+    }
+
+    #[test]
+    fn timer_saves_leave_backups_alone() {
+        let (_app_dir, mut app) = temp_app();
+        app.save().unwrap();
+        app.job_board.context = Some("first".to_owned());
+        app.save().unwrap();
+        assert_eq!(app.backup_paths().unwrap().len(), 1);
+
+        app.job_board.context = Some("second".to_owned());
+        app.save_timers().unwrap();
+        assert_eq!(app.backup_paths().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn reminder_interval_grows_up_to_a_cap() {
+        let (_app_dir, mut app) = temp_app();
+        push_timeboxed(&mut app, "overrun", 1, at(1, 9, 0));
+        let times = reminder_times(&mut app, at(1, 9, 2), 45);
+        assert_eq!(gaps(&times), [35, 65, 125, 245, 485, 605, 605]);
+    }
+
+    #[test]
+    fn each_reminder_policy_sets_its_own_pace() {
+        let policy_times = |policy| {
+            let (_app_dir, mut app) = temp_app();
+            push_timeboxed(&mut app, "overrun", 1, at(1, 9, 0));
+            app.job_board.active_stack[0].reminder_policy = policy;
+            reminder_times(&mut app, at(1, 9, 2), 10)
+        };
+        assert_eq!(policy_times(Some(ReminderPolicy::Once)), [at(1, 9, 2)]);
+        let every = policy_times(Some(ReminderPolicy::Every(StdDuration::from_secs(120))));
+        assert_eq!(gaps(&every), [125, 125, 125, 125]);
+        let backoff = policy_times(Some(ReminderPolicy::Backoff));
+        assert_eq!(gaps(&backoff), [35, 65, 125, 245]);
+        // Jobs without a policy of their own back off
+        assert_eq!(policy_times(None), backoff);
+    }
+
+    #[test]
+    fn expirations_at_once_share_one_alarm() {
+        let (_app_dir, mut app) = temp_app();
+        push_suspended(&mut app, "call back", 0);
+        push_suspended(&mut app, "water plants", 0);
+        for stack in &mut app.job_board.suspended_stacks {
+            stack.timer = Some(at(1, 9, 0));
+        }
+        push_timeboxed(&mut app, "write report", 30, at(1, 8, 0));
+
+        let timer_state = app.update_timers_at(at(1, 9, 1)).unwrap();
+        assert!(timer_state.send_alarm);
+        let mut reminders = timer_state.reminders;
+        reminders.sort();
+        assert_eq!(reminders, ["call back", "water plants", "write report"]);
+        assert!(!app.update_timers_at(at(1, 9, 1)).unwrap().send_alarm);
+    }
+
+    #[test]
+    fn work_mode_is_held_off_outside_work_hours() {
+        let (_app_dir, mut app) = scheduled_app();
+        app.job_board.work_state = WorkState::SlackingSince(at(4, 16, 0));
+        let evening = app.update_timers_at(at(4, 18, 0)).unwrap();
+        assert!(!evening.send_alarm);
+        assert!(app.job_board.off_hours);
+        let weekend = app.update_timers_at(at(5, 12, 0)).unwrap();
+        assert!(!weekend.send_alarm);
+
+        // Monday morning, work mode is back without being turned on again
+        let monday = app.update_timers_at(at(7, 9, 0)).unwrap();
+        assert!(!monday.send_alarm);
+        assert!(!app.job_board.off_hours);
+        assert_eq!(app.job_board.work_state, WorkState::Working);
+        app.update_timers_at(at(7, 9, 1)).unwrap();
+        assert!(!app.update_timers_at(at(7, 9, 4)).unwrap().send_alarm);
+        assert!(app.update_timers_at(at(7, 9, 7)).unwrap().send_alarm);
+    }
+
+    #[test]
+    fn forced_work_mode_runs_until_work_hours() {
+        let (_app_dir, mut app) = scheduled_app();
+        app.job_board.work_state = WorkState::Working;
+        app.job_board.work_forced = true;
+        app.update_timers_at(at(5, 12, 0)).unwrap();
+        assert!(app.update_timers_at(at(5, 12, 6)).unwrap().send_alarm);
+
+        // Once the hours start, work mode follows them again
+        app.update_timers_at(at(7, 9, 0)).unwrap();
+        assert!(!app.job_board.work_forced);
+        app.update_timers_at(at(7, 17, 0)).unwrap();
+        assert!(app.job_board.off_hours);
+    }
+
+    #[test]
+    fn looking_at_the_board_counts_as_activity() {
+        let (app_dir, mut app) = temp_app();
+        app.settings.auto_suspend_after = Some(StdDuration::from_secs(60 * 60));
+        app.create_job("write report".to_owned(), PushOptions::default())
+            .unwrap();
+        let start = Utc::now();
+
+        let mut viewer = WydApplication::load_read_only(app_dir.path().to_owned()).unwrap();
+        viewer.record_interaction_at(start + Duration::minutes(50));
+        assert_eq!(app.auto_suspend_inactive(start + Duration::minutes(100)), None);
+        assert_eq!(
+            app.auto_suspend_inactive(start + Duration::minutes(111)),
+            Some("write report".to_owned())
+        );
+        assert!(app.job_board.active_stack.is_empty());
+    }
+
+    #[test]
+    fn prompts_let_other_writers_in() {
+        let (app_dir, mut app) = temp_app();
+        app.hold_state_lock(StateLock::acquire(app_dir.path()).unwrap());
+        assert_eq!(app.unlocked(|| "unchanged"), Some("unchanged"));
+
+        // Another command pushes while this one waits on the user
+        let answer = app.unlocked(|| {
+            let _state_lock = StateLock::acquire(app_dir.path()).unwrap();
+            let mut other = WydApplication::load(app_dir.path().to_owned()).unwrap();
+            other.create_job("pushed meanwhile".to_owned(), PushOptions::default())
+                .unwrap();
+        });
+        assert_eq!(answer, None);
+        assert_eq!(JobBoard::load(app_dir.path()).active_stack.len(), 1);
+        // The lock is back, so nobody else can get in until this command is done
+        assert!(StateLock::try_acquire(app_dir.path()).is_none());
+    }
+
+    #[test]
+    fn unreadable_history_still_pushes() {
+        let (_app_dir, mut app) = temp_app();
+        fs::write(app.completion_records_path(), "not a record\n").unwrap();
+        app.create_job_from_history("write report".to_owned(), PushOptions::default(), false)
+            .unwrap();
+        app.create_job_from_history("review report".to_owned(), PushOptions::default(), true)
+            .unwrap();
+        assert_eq!(app.job_board.active_stack.len(), 2);
+    }
+
+    fn steps(steps: &[&str]) -> Vec<String> {
+        steps.iter().map(|step| step.to_string()).collect()
+    }
+
+    #[test]
+    fn checklist_saves_once_with_first_step_on_top() {
+        let (app_dir, mut app) = temp_app();
+        app.create_checklist(steps(&["one", "two", "three"]), PushOptions::default())
+            .unwrap();
+        let saved = JobBoard::load(app_dir.path());
+        let labels: Vec<&str> = saved.active_stack.iter().map(|job| job.label.as_str()).collect();
+        assert_eq!(labels, ["three", "two", "one"]);
+    }
+
+    #[test]
+    fn checklist_leaves_outer_deferral_in_place() {
+        let (app_dir, mut app) = temp_app();
+        app.defer_saves();
+        app.create_checklist(steps(&["one", "two"]), PushOptions::default())
+            .unwrap();
+        assert!(app.defer_saves);
+        assert!(JobBoard::load(app_dir.path()).active_stack.is_empty());
+        app.finish_deferred_saves().unwrap();
+        assert_eq!(JobBoard::load(app_dir.path()).active_stack.len(), 2);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn speech_text_is_never_an_option() {
+        let command = speech_command("-v whisper");
+        let args: Vec<&OsStr> = command.get_args().collect();
+        assert_eq!(args, [OsStr::new("--"), OsStr::new("-v whisper")]);
+    }
+
+    #[test]
+    fn json_listing_reads_back_with_its_schema() {
+        let (_app_dir, mut app) = temp_app();
+        app.create_job("call plumber".to_owned(), PushOptions::default())
+            .unwrap();
+        let options = SuspendOptions {
+            reason: "waiting on a callback".to_owned(),
+            timer: Some(at(4, 9, 0)),
+            ..SuspendOptions::default()
+        };
+        app.job_board.suspend_current(options).unwrap();
+        push_timeboxed(&mut app, "plan sprint", 30, Utc::now());
+        app.job_board.active_stack[0].tags = vec!["work".to_owned()];
+
+        let listing: serde_json::Value =
+            serde_json::from_str(&export::json_listing(&app.job_board).unwrap()).unwrap();
+        assert_eq!(listing["schema"], 1);
+        let active = &listing["active"][0];
+        let job = &app.job_board.active_stack[0];
+        assert_eq!(active["id"], job.id.as_str());
+        assert_eq!(active["label"], "plan sprint");
+        assert_eq!(active["begin"], job.begin_date.timestamp());
+        let remaining = active["timebox_remaining"].as_i64().unwrap();
+        assert!((1790..=1800).contains(&remaining));
+        assert_eq!(active["expired"], false);
+        assert_eq!(active["tags"], serde_json::json!(["work"]));
+
+        let stack = &listing["suspended"][0];
+        assert_eq!(stack["id"], app.job_board.suspended_stacks[0].id());
+        assert_eq!(stack["reason"], "waiting on a callback");
+        assert_eq!(stack["timer"], at(4, 9, 0).timestamp());
+        assert!(stack["age"].as_i64().unwrap() >= 0);
+        assert_eq!(stack["jobs"][0]["label"], "call plumber");
+        assert_eq!(stack["jobs"][0]["timebox_remaining"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn missing_sound_device_is_an_error() {
+        let error = play_alarm_on(|| Err(StreamError::NoDevice), 1.0).unwrap_err();
+        assert!(format!("{:#}", error).starts_with("No audio output device is available"));
+    }
+
+    #[test]
+    fn timeboxes_run_from_now_or_from_the_start() {
+        let (_app_dir, mut app) = temp_app();
+        let started = Utc::now() - Duration::hours(1);
+        push_timeboxed(&mut app, "long haul", 10, started);
+        let half_hour = Some(StdDuration::from_secs(30 * 60));
+
+        app.apply_timebox(half_hour, true).unwrap();
+        let job = &app.job_board.active_stack[0];
+        assert_eq!(job.begin_date, started);
+        assert!(job.timebox_expired());
+
+        app.apply_timebox(half_hour, false).unwrap();
+        let job = &app.job_board.active_stack[0];
+        assert!(job.begin_date > started + Duration::minutes(59));
+        assert!(!job.timebox_expired());
+    }
+
+    #[test]
+    fn resuming_a_copy_leaves_the_original_suspended() {
+        let (_app_dir, mut app) = temp_app();
+        push_suspended(&mut app, "water plants", 0);
+        push_suspended(&mut app, "take out bins", 0);
+        let original_id = app.job_board.suspended_stacks[0].data[0].id.clone();
+        let options = ResumeOptions {
+            keep_suspended: true,
+            ..ResumeOptions::default()
+        };
+        app.resume_job_named("water", options).unwrap();
+
+        assert_eq!(app.job_board.suspended_stacks.len(), 2);
+        assert_eq!(suspended_labels(&app.job_board), ["water plants", "take out bins"]);
+        let copy = &app.job_board.active_stack[0];
+        assert_eq!(copy.label, "water plants");
+        assert_ne!(copy.id, original_id);
+    }
+
+    #[test]
+    fn newest_and_oldest_go_by_suspend_date() {
+        let (_app_dir, mut app) = temp_app();
+        let now = Utc::now();
+        for (label, hours_ago) in [("middle", 2), ("newest", 1), ("oldest", 3)] {
+            push_suspended(&mut app, label, 0);
+            let stacks = &mut app.job_board.suspended_stacks;
+            let stack = stacks.iter_mut().find(|stack| stack.data[0].label == label).unwrap();
+            stack.date_suspended = now - Duration::hours(hours_ago);
+        }
+        let resumed = |app: &mut WydApplication, newest| {
+            let options = ResumeOptions {
+                newest,
+                oldest: !newest,
+                ..ResumeOptions::default()
+            };
+            app.resume_job_named("", options).unwrap();
+            app.job_board.active_stack.pop().unwrap().label
+        };
+        assert_eq!(resumed(&mut app, true), "newest");
+        assert_eq!(resumed(&mut app, false), "oldest");
+        assert_eq!(resumed(&mut app, true), "middle");
+        assert!(app.job_board.suspended_stacks.is_empty());
+    }
+
+    #[test]
+    fn tag_time_credits_the_override_tag() {
+        let (_app_dir, mut app) = temp_app();
+        let options = PushOptions {
+            tags: vec!["code".to_owned()],
+            ..PushOptions::default()
+        };
+        app.create_job("review pull request".to_owned(), options).unwrap();
+        app.job_board.active_stack[0].begin_date = Utc::now() - Duration::minutes(20);
+        let options = DoneOptions {
+            count: 1,
+            time_tag: Some("meeting".to_owned()),
+            ..DoneOptions::default()
+        };
+        app.complete_current_jobs(options).unwrap();
+
+        let records: Vec<CompletionRecord> =
+            history::read_records(&app.completion_records_path()).unwrap();
+        assert_eq!(records[0].tags, ["code"]);
+        let now = Utc::now();
+        let totals = stats::time_by_tag(&records, now - Duration::hours(1), now, None);
+        let credited: Vec<&str> = totals.keys().map(String::as_str).collect();
+        assert_eq!(credited, ["meeting"]);
+        assert!(totals["meeting"] >= Duration::minutes(20));
+    }
+
+    #[test]
+    fn again_repeats_the_last_finished_job() {
+        let (_app_dir, mut app) = temp_app();
+        app.repeat_last_completion(false).unwrap();
+        assert_eq!(app.rejection_count(), 1);
+
+        let options = PushOptions {
+            timebox: Some(StdDuration::from_secs(25 * 60)),
+            tags: vec!["code".to_owned()],
+            ..PushOptions::default()
+        };
+        app.create_job("review pull request".to_owned(), options).unwrap();
+        app.job_board.active_stack[0].begin_date = Utc::now() - Duration::minutes(20);
+        let finished = app.job_board.active_stack[0].clone();
+        app.complete_current_jobs(DoneOptions { count: 1, ..DoneOptions::default() })
+            .unwrap();
+
+        app.repeat_last_completion(false).unwrap();
+        app.repeat_last_completion(true).unwrap();
+        let stack = &app.job_board.active_stack;
+        assert_eq!(stack.len(), 2);
+        for job in stack {
+            assert_eq!(job.label, "review pull request");
+            assert_eq!(job.tags, ["code"]);
+            assert_ne!(job.id, finished.id);
+            assert!(job.begin_date > finished.begin_date + Duration::minutes(10));
+        }
+        assert_ne!(stack[0].id, stack[1].id);
+        assert_eq!(stack[0].timebox, None);
+        assert_eq!(stack[1].timebox, finished.timebox);
+        assert_eq!(app.rejection_count(), 1);
+    }
+
+    #[test]
+    fn resume_and_timebox_boxes_the_new_top_job() {
+        let (_app_dir, mut app) = temp_app();
+        push_suspended(&mut app, "draft proposal", 0);
+        let options = ResumeOptions {
+            timebox: Some(StdDuration::from_secs(25 * 60)),
+            ..ResumeOptions::default()
+        };
+        app.resume_job_named("draft", options).unwrap();
+
+        let job = &app.job_board.active_stack[0];
+        assert_eq!(job.label, "draft proposal");
+        assert_eq!(job.timebox, Some(StdDuration::from_secs(25 * 60)));
+        assert!(!job.timebox_expired());
+        assert!(app.job_board.suspended_stacks.is_empty());
+    }
+
+    #[test]
+    fn resume_and_timebox_keeps_timeboxed_jobs_free_of_subtasks() {
+        let (_app_dir, mut app) = temp_app();
+        push_suspended(&mut app, "draft proposal", 0);
+        push_timeboxed(&mut app, "standup", 15, Utc::now());
+        let options = ResumeOptions {
+            stack_into_current: true,
+            timebox: Some(StdDuration::from_secs(25 * 60)),
+            ..ResumeOptions::default()
+        };
+        app.resume_job_named("draft", options).unwrap();
+
+        assert_eq!(app.rejection_count(), 1);
+        assert_eq!(app.job_board.active_stack.len(), 1);
+        assert_eq!(suspended_labels(&app.job_board), ["draft proposal"]);
+    }
+
+    #[test]
+    fn editing_a_reason_leaves_the_rest_of_the_stack() {
+        let (_app_dir, mut app) = temp_app();
+        push_suspended(&mut app, "call Bob", 30);
+        app.job_board.suspended_stacks[0].reason = "waiting on Bob".to_owned();
+        let mut expected = app.job_board.suspended_stacks[0].clone();
+        expected.reason = "Bob replied, call him back".to_owned();
+
+        let reason = expected.reason.clone();
+        app.edit_suspension_reason("Bob", reason, false, false).unwrap();
+        let stack = &app.job_board.suspended_stacks[0];
+        assert_eq!(stack.reason, "Bob replied, call him back");
+        assert_eq!(ron::to_string(stack).unwrap(), ron::to_string(&expected).unwrap());
+
+        app.edit_suspension_reason("alice", "anything".to_owned(), false, false)
+            .unwrap();
+        assert_eq!(app.rejection_count(), 1);
+    }
+
+    #[test]
+    fn stacking_into_current_needs_an_untimed_top_job() {
+        let (_app_dir, mut app) = temp_app();
+        push_suspended(&mut app, "fix typo", 0);
+        let into_current = || ResumeOptions {
+            stack_into_current: true,
+            ..ResumeOptions::default()
+        };
+
+        app.resume_job_named("typo", into_current()).unwrap();
+        assert_eq!(app.rejection_count(), 1);
+
+        push_timeboxed(&mut app, "edit chapter", 30, Utc::now());
+        app.resume_job_named("typo", into_current()).unwrap();
+        assert_eq!(app.rejection_count(), 2);
+        assert_eq!(suspended_labels(&app.job_board), ["fix typo"]);
+
+        app.apply_timebox(None, false).unwrap();
+        app.resume_job_named("typo", into_current()).unwrap();
+        assert_eq!(app.rejection_count(), 2);
+        let labels: Vec<&str> =
+            app.job_board.active_stack.iter().map(|job| job.label.as_str()).collect();
+        assert_eq!(labels, ["edit chapter", "fix typo"]);
+    }
+
+    #[test]
+    fn mv_suspends_and_resumes_exactly_by_id() {
+        let (_app_dir, mut app) = temp_app();
+        for label in ["outline", "outline intro", "outline summary"] {
+            app.create_job(label.to_owned(), PushOptions::default())
+                .unwrap();
+        }
+        let middle_id = format!("#{}", app.job_board.active_stack[1].id);
+        let options = SuspendOptions {
+            reason: "blocked".to_owned(),
+            ..SuspendOptions::default()
+        };
+        app.move_job(&middle_id, MoveTarget::Suspended, options).unwrap();
+        assert_eq!(app.job_board.active_stack.len(), 1);
+        let stack = &app.job_board.suspended_stacks[0];
+        assert_eq!(stack.reason, "blocked");
+        let labels: Vec<&str> = stack.data.iter().map(|job| job.label.as_str()).collect();
+        assert_eq!(labels, ["outline intro", "outline summary"]);
+
+        app.move_job(&middle_id, MoveTarget::Suspended, SuspendOptions::default())
+            .unwrap();
+        assert_eq!(app.rejection_count(), 1);
+
+        app.move_job(&middle_id, MoveTarget::Active, SuspendOptions::default())
+            .unwrap();
+        assert!(app.job_board.suspended_stacks.is_empty());
+        assert_eq!(app.job_board.active_stack.len(), 3);
+
+        app.move_job("#000000", MoveTarget::Active, SuspendOptions::default())
+            .unwrap();
+        assert_eq!(app.rejection_count(), 2);
+    }
+
+    #[test]
+    fn silent_stacks_never_alarm() {
+        let (_app_dir, mut app) = temp_app();
+        push_suspended(&mut app, "renew passport", 0);
+        let stack = &mut app.job_board.suspended_stacks[0];
+        stack.silent = true;
+        stack.timer = Some(at(1, 9, 0));
+
+        assert!(reminder_times(&mut app, at(1, 9, 0), 60).is_empty());
+        assert!(app.update_timers_at(at(8, 9, 0)).unwrap().reminders.is_empty());
+    }
+
+    #[test]
+    fn cancel_logs_and_records_a_cancellation() {
+        let (_app_dir, mut app) = temp_app();
+        for label in ["write essay", "find sources"] {
+            app.create_job(label.to_owned(), PushOptions::default())
+                .unwrap();
+        }
+        let reason = Some("out of scope".to_owned());
+        app.cancel_job_named(Some("essay"), reason, false, false)
+            .unwrap();
+
+        assert!(app.job_board.active_stack.is_empty());
+        let log = fs::read_to_string(app.current_log_path()).unwrap();
+        assert!(log.contains("Cancelled job \"find sources\""));
+        assert!(log.contains("Cancelled job \"write essay\""));
+        assert!(log.contains("reason: out of scope"));
+        let records: Vec<CompletionRecord> =
+            history::read_records(&app.completion_records_path()).unwrap();
+        let labels: Vec<&str> = records.iter().map(|record| record.label.as_str()).collect();
+        assert_eq!(labels, ["find sources", "write essay"]);
+        assert!(records.iter().all(|record| record.cancelled));
+        assert_eq!(records[1].reason.as_deref(), Some("out of scope"));
+    }
+
+    #[test]
+    fn low_priority_work_counts_as_slacking() {
+        let (_app_dir, mut app) = temp_app();
+        app.job_board.work_state = WorkState::Working;
+        let options = PushOptions {
+            priority: Priority::High,
+            ..PushOptions::default()
+        };
+        app.create_job("ship release".to_owned(), options).unwrap();
+        push_timeboxed(&mut app, "tidy inbox", 120, at(1, 9, 0));
+        app.job_board.active_stack[1].priority = Priority::Low;
+
+        // A high priority job doesn't count while the timeboxed work on top is busywork
+        app.update_timers_at(at(1, 9, 0)).unwrap();
+        assert_eq!(app.job_board.work_state, WorkState::SlackingSince(at(1, 9, 0)));
+        assert!(app.update_timers_at(at(1, 9, 6)).unwrap().send_alarm);
+
+        app.job_board.active_stack[1].priority = Priority::Normal;
+        app.update_timers_at(at(1, 9, 7)).unwrap();
+        assert_eq!(app.job_board.work_state, WorkState::Working);
+    }
+
+    #[test]
+    fn paused_jobs_accrue_no_time() {
+        let (_app_dir, mut app) = temp_app();
+        let options = PushOptions {
+            timebox: Some(StdDuration::from_secs(10 * 60)),
+            start_paused: true,
+            ..PushOptions::default()
+        };
+        app.create_job("next up".to_owned(), options).unwrap();
+        let job = &mut app.job_board.active_stack[0];
+        job.begin_date = at(1, 9, 0);
+        job.paused_since = Some(at(1, 9, 0));
+
+        let job = &app.job_board.active_stack[0];
+        assert_eq!(job.elapsed(at(1, 12, 0)), Duration::zero());
+        assert_eq!(job.timebox_remaining(), Some(StdDuration::from_secs(10 * 60)));
+        assert!(!app.update_timers_at(at(1, 12, 0)).unwrap().send_alarm);
+
+        let job = &mut app.job_board.active_stack[0];
+        job.unpause(at(1, 12, 0));
+        assert_eq!(job.elapsed(at(1, 12, 5)), Duration::minutes(5));
+        assert!(app.update_timers_at(at(1, 12, 11)).unwrap().send_alarm);
+    }
+
+    #[test]
+    fn resume_last_restores_the_newest_stack_as_it_was() {
+        let (_app_dir, mut app) = temp_app();
+        let started = Utc::now() - Duration::hours(3);
+        for (label, hours_ago) in [("older", 2), ("newer", 1)] {
+            for label in [label.to_owned(), format!("{} subtask", label)] {
+                app.create_job(label, PushOptions::default()).unwrap();
+            }
+            for job in &mut app.job_board.active_stack {
+                job.begin_date = started;
+            }
+            app.job_board.suspend_at(0, SuspendOptions::default()).unwrap();
+            let stacks = &mut app.job_board.suspended_stacks;
+            let stack = stacks.iter_mut().find(|stack| stack.data[0].label == label).unwrap();
+            stack.date_suspended = Utc::now() - Duration::hours(hours_ago);
+        }
+
+        // What `resume --last` asks for
+        let options = ResumeOptions {
+            newest: true,
+            keep_times: true,
+            ..ResumeOptions::default()
+        };
+        app.resume_job_named("", options).unwrap();
+        let labels: Vec<&str> =
+            app.job_board.active_stack.iter().map(|job| job.label.as_str()).collect();
+        assert_eq!(labels, ["newer", "newer subtask"]);
+        assert!(app.job_board.active_stack.iter().all(|job| job.begin_date == started));
+        assert_eq!(suspended_labels(&app.job_board), ["older"]);
+    }
+
+    #[test]
+    fn suspend_to_moves_a_job_between_boards() {
+        let (app_dir, mut app) = temp_app();
+        for label in ["write report", "buy milk"] {
+            app.create_job(label.to_owned(), PushOptions::default())
+                .unwrap();
+        }
+        let other_dir = tempfile::tempdir().unwrap();
+        let personal = other_dir.path().join("personal");
+        let options = SuspendOptions {
+            reason: "after work".to_owned(),
+            ..SuspendOptions::default()
+        };
+        app.suspend_job_to("milk", options, false, false, &personal)
+            .unwrap();
+
+        let work_board = JobBoard::load(app_dir.path());
+        let labels: Vec<&str> =
+            work_board.active_stack.iter().map(|job| job.label.as_str()).collect();
+        assert_eq!(labels, ["write report"]);
+        let personal_board = JobBoard::load(&personal);
+        assert_eq!(suspended_labels(&personal_board), ["buy milk"]);
+        assert_eq!(personal_board.suspended_stacks[0].reason, "after work");
+
+        app.suspend_job_to("", SuspendOptions::default(), false, false, app_dir.path())
+            .unwrap();
+        assert_eq!(app.rejection_count(), 1);
+        assert_eq!(app.job_board.active_stack.len(), 1);
+    }
+
+    #[test]
+    fn tagged_notes_can_be_found_by_tag() {
+        let (_app_dir, app) = temp_app();
+        app.add_log_note("order more paper".to_owned(), Some("office".to_owned()));
+        app.add_log_note("call landlord".to_owned(), None);
+        app.add_log_note("book a room".to_owned(), Some("office".to_owned()));
+        app.add_log_note("buy paper towels".to_owned(), Some("home".to_owned()));
+
+        let texts = |tag, pattern| -> Vec<String> {
+            let notes = app.matching_notes(tag, pattern).unwrap();
+            notes.into_iter().map(|note| note.text).collect()
+        };
+        assert_eq!(texts(Some("office"), ""), ["order more paper", "book a room"]);
+        assert_eq!(texts(Some("office"), "paper"), ["order more paper"]);
+        assert_eq!(texts(None, "paper"), ["order more paper", "buy paper towels"]);
+        assert!(texts(Some("garden"), "").is_empty());
+
+        let log = fs::read_to_string(app.current_log_path()).unwrap();
+        assert!(log.contains("[office] order more paper"));
+        assert!(log.contains("[home] buy paper towels"));
+        assert!(!log.contains("] call landlord"));
+    }
+
+    #[test]
+    fn replacing_a_note_rewrites_the_store_and_the_log() {
+        let (_app_dir, mut app) = temp_app();
+        app.add_log_note("order more paepr".to_owned(), Some("office".to_owned()));
+        app.add_log_note("call landlord".to_owned(), None);
+        let notes: Vec<NoteRecord> = history::read_records(&app.notes_path()).unwrap();
+
+        app.replace_note(&notes[0], "order more paper".to_owned()).unwrap();
+        let texts: Vec<String> = history::read_records::<NoteRecord>(&app.notes_path())
+            .unwrap()
+            .into_iter()
+            .map(|note| note.text)
+            .collect();
+        assert_eq!(texts, ["order more paper", "call landlord"]);
+        let log = fs::read_to_string(app.current_log_path()).unwrap();
+        assert!(log.contains("[office] order more paper"));
+        assert!(!log.contains("paepr"));
+
+        // A note that's gone from the store can't be edited
+        app.replace_note(&notes[0], "order toner".to_owned()).unwrap();
+        assert_eq!(app.rejection_count(), 1);
+    }
+
+    #[test]
+    fn half_done_timeboxes_resume_with_the_time_left() {
+        let minutes_left = |keep_times, timebox_remaining| {
+            let (_app_dir, mut app) = temp_app();
+            push_timeboxed(&mut app, "review", 20, Utc::now() - Duration::minutes(70));
+            app.job_board.suspend_current(SuspendOptions::default()).unwrap();
+            app.job_board.suspended_stacks[0].date_suspended = Utc::now() - Duration::minutes(60);
+            let options = ResumeOptions {
+                keep_times,
+                timebox_remaining,
+                ..ResumeOptions::default()
+            };
+            app.resume_job_named("review", options).unwrap();
+            let remaining = app.job_board.active_stack[0].timebox_remaining().unwrap();
+            (remaining.as_secs() + 30) / 60
+        };
+        assert_eq!(minutes_left(false, false), 10);
+        assert_eq!(minutes_left(true, true), 10);
+        // Keeping the times alone leaves the suspension counted against the timebox
+        assert_eq!(minutes_left(true, false), 0);
+    }
+
+    #[test]
+    fn anonymized_exports_keep_no_label_text() {
+        let (app_dir, mut app) = temp_app();
+        let secrets = [
+            "quokka merger",
+            "zanzibar",
+            "acquisition",
+            "waiting on legal",
+            "wombat audit",
+            "scrapped by ceo",
+            "payroll",
+            "intranet.example.com",
+        ];
+        let options = PushOptions {
+            tags: vec!["acquisition".to_owned()],
+            links: vec![Url::parse("https://intranet.example.com/deal").unwrap()],
+            ..PushOptions::default()
+        };
+        app.create_job("quokka merger".to_owned(), options).unwrap();
+        app.create_job("zanzibar".to_owned(), PushOptions::default())
+            .unwrap();
+        let options = SuspendOptions {
+            reason: "waiting on legal".to_owned(),
+            ..SuspendOptions::default()
+        };
+        app.job_board.suspend_current(options).unwrap();
+        app.create_job("wombat audit".to_owned(), PushOptions::default())
+            .unwrap();
+        let options = DoneOptions {
+            cancelled: true,
+            count: 1,
+            time_tag: Some("payroll".to_owned()),
+            reason: Some("scrapped by ceo".to_owned()),
+            ..DoneOptions::default()
+        };
+        app.complete_current_jobs(options).unwrap();
+        let begin = app.job_board.active_stack[0].begin_date.timestamp().to_string();
+
+        for format in [ExportFormat::Json, ExportFormat::Ron, ExportFormat::Csv] {
+            let path = app_dir.path().join("export.out");
+            app.export(format, Some(path.clone()), true, None, None, true)
+                .unwrap();
+            let exported = fs::read_to_string(&path).unwrap();
+            for secret in secrets {
+                assert!(!exported.contains(secret), "{:?} leaked {:?}", format, secret);
+            }
+            assert!(exported.contains("task-"));
+            if format != ExportFormat::Csv {
+                assert!(exported.contains(&begin));
+            }
+        }
+    }
+
+    #[test]
+    fn breaks_hold_off_alarms_until_they_expire() {
+        let (_app_dir, mut app) = temp_app();
+        app.take_break(StdDuration::from_secs(60)).unwrap();
+        assert_eq!(app.rejection_count(), 1);
+
+        app.job_board.work_state = WorkState::BreakUntil(at(1, 9, 30));
+        assert!(reminder_times(&mut app, at(1, 9, 0), 30).is_empty());
+        assert!(app.describe_work_state(at(1, 9, 20)).contains("(10m left)"));
+
+        let break_over = app.update_timers_at(at(1, 9, 30)).unwrap();
+        assert!(break_over.send_alarm);
+        assert_eq!(break_over.reminders, ["Your break is over"]);
+        assert_eq!(app.job_board.work_state, WorkState::Working);
+
+        // Back to work, where slacking is noticed as usual
+        app.update_timers_at(at(1, 9, 31)).unwrap();
+        assert_eq!(app.job_board.work_state, WorkState::SlackingSince(at(1, 9, 31)));
+        assert!(app.update_timers_at(at(1, 9, 37)).unwrap().send_alarm);
+    }
+
+    #[test]
+    fn read_only_loads_save_ids_given_to_an_old_board() {
+        let app_dir = tempfile::tempdir().unwrap();
+        let old_board = "(work_state: Off, active_stack: [(label: \"hello\", \
+            begin_date: 1600000000, timebox: None, last_notification: None)], \
+            suspended_stacks: [])";
+        fs::write(app_dir.path().join("jobs.ron"), old_board).unwrap();
+
+        let ids: Vec<String> = (0..2)
+            .map(|_| {
+                let app = WydApplication::load_read_only(app_dir.path().to_owned()).unwrap();
+                assert!(app.read_only);
+                app.job_board.active_stack[0].id.clone()
+            })
+            .collect();
+        assert_eq!(ids[0], ids[1]);
+        let saved = JobBoard::load_read_only(app_dir.path()).unwrap();
+        assert_eq!(saved.schema_version, crate::job_board::SCHEMA_VERSION);
+        assert_eq!(saved.active_stack[0].id, ids[0]);
+    }
 }